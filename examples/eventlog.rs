@@ -32,7 +32,7 @@ impl myo::EventListener for EventLogger
         let device = event.device();
         println!("{} {} Arm Synced! on {:?}, +x {:?}, warm? {:?}, estimated device rotation {} rad",
             event.timestamp(), device.raw_id(), event.arm(), event.xdirection(), event.warmup_state(), event.rotation_on_arm());
-        /// Request RSSI and Battery Level immediately.
+        // Request RSSI and Battery Level immediately.
         device.request_rssi().unwrap();
         device.request_battery_level().unwrap();
         // device.set_stream_emg(true).unwrap();
@@ -0,0 +1,397 @@
+//! Fake libmyo: a scriptable implementation of the libmyo C API for tests.
+//!
+//! Built as a `cdylib`, this crate exports every `libmyo_*` function declared by `myo`,
+//! plus `fakemyo_*` control functions to queue events, inject errors and inspect received calls.
+//! Load it into `myo` with the `dynamic` feature(`myo::load_library(fake_libmyo::library_path())`)
+//! and drive it through `Controller`.
+
+#![allow(clippy::missing_safety_doc)]
+
+extern crate libc;
+extern crate libloading;
+
+use libc::{c_void, c_char, c_int, c_uint, c_float};
+use std::ffi::{CStr, CString};
+use std::collections::{VecDeque, HashMap};
+use std::sync::{Mutex, MutexGuard};
+use std::path::PathBuf;
+
+/// Function result codes(mirrors `myo::ResultCode`).
+#[repr(C)] #[derive(Debug, Clone, Copy, PartialEq, Eq)] pub enum ResultCode
+{
+    Success, Error, ErrorInvalidArgument, Runtime
+}
+
+/// Scripted event data
+#[repr(C)] #[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FakeEvent
+{
+    /// Event Type(`myo::EventType as u32`)
+    pub event_type: u32,
+    pub timestamp: u64,
+    /// Device identifier returned as `libmyo_myo_t`; must not be zero
+    pub myo: usize,
+    pub mac_address: u64,
+    /// Major, Minor, Patch, HardwareRevision
+    pub firmware_version: [c_uint; 4],
+    pub arm: c_int,
+    pub x_direction: c_int,
+    pub warmup_state: c_int,
+    pub warmup_result: c_int,
+    pub rotation_on_arm: c_float,
+    /// x, y, z, w
+    pub orientation: [c_float; 4],
+    pub accelerometer: [c_float; 3],
+    pub gyroscope: [c_float; 3],
+    pub pose: c_int,
+    pub rssi: i8,
+    pub battery_level: u8,
+    pub emg: [i8; 8]
+}
+impl FakeEvent
+{
+    /// Event with all payload fields zeroed
+    pub fn new(event_type: u32, timestamp: u64, myo: usize, mac_address: u64) -> Self
+    {
+        FakeEvent
+        {
+            event_type, timestamp, myo, mac_address,
+            firmware_version: [0; 4], arm: 0, x_direction: 0, warmup_state: 0, warmup_result: 0, rotation_on_arm: 0.0,
+            orientation: [0.0; 4], accelerometer: [0.0; 3], gyroscope: [0.0; 3], pose: 0, rssi: 0, battery_level: 0, emg: [0; 8]
+        }
+    }
+}
+
+/// Kinds of recorded calls
+#[repr(C)] #[derive(Debug, Clone, Copy, PartialEq, Eq)] pub enum CallKind
+{
+    SetLockingPolicy, Vibrate, RequestRSSI, RequestBatteryLevel, SetStreamEMG, Unlock, Lock, NotifyUserAction
+}
+/// Recorded call
+#[repr(C)] #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FakeCall
+{
+    pub kind: CallKind,
+    /// Target device(0 for hub calls)
+    pub myo: usize,
+    /// Enum argument of the call as an integer(0 if none)
+    pub argument: c_int
+}
+
+struct QueuedEvent { event: FakeEvent, name: CString }
+struct ErrorDetails { kind: ResultCode, message: CString }
+struct State
+{
+    hubs: Vec<usize>, events: VecDeque<QueuedEvent>, calls: Vec<FakeCall>,
+    failures: HashMap<String, (ResultCode, CString)>, next_hub: usize
+}
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+fn state() -> MutexGuard<'static, Option<State>>
+{
+    let mut s = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if s.is_none()
+    {
+        *s = Some(State { hubs: Vec::new(), events: VecDeque::new(), calls: Vec::new(), failures: HashMap::new(), next_hub: 1 });
+    }
+    s
+}
+macro_rules! with_state { ($s: ident => $e: expr) => {{ let mut g = state(); let $s = g.as_mut().unwrap(); $e }} }
+
+unsafe fn fail(out_error: *mut *mut c_void, kind: ResultCode, message: CString) -> ResultCode
+{
+    if !out_error.is_null()
+    {
+        *out_error = Box::into_raw(Box::new(ErrorDetails { kind, message })) as *mut c_void;
+    }
+    kind
+}
+/// Consume an injected failure for `function`
+unsafe fn injected(function: &str, out_error: *mut *mut c_void) -> Option<ResultCode>
+{
+    let f = with_state!(s => s.failures.remove(function));
+    f.map(|(kind, message)| fail(out_error, kind, message))
+}
+unsafe fn check_hub(hub: *mut c_void, out_error: *mut *mut c_void) -> Option<ResultCode>
+{
+    if hub.is_null() { return Some(fail(out_error, ResultCode::ErrorInvalidArgument, CString::new("hub is NULL").unwrap())); }
+    if !with_state!(s => s.hubs.contains(&(hub as usize)))
+    {
+        return Some(fail(out_error, ResultCode::Error, CString::new("hub is not a valid hub").unwrap()));
+    }
+    None
+}
+unsafe fn device_call(function: &str, kind: CallKind, myo: *mut c_void, argument: c_int, out_error: *mut *mut c_void) -> ResultCode
+{
+    if let Some(r) = injected(function, out_error) { return r; }
+    if myo.is_null() { return fail(out_error, ResultCode::ErrorInvalidArgument, CString::new("myo is NULL").unwrap()); }
+    with_state!(s => s.calls.push(FakeCall { kind, myo: myo as usize, argument }));
+    ResultCode::Success
+}
+unsafe fn event<'a>(event: *const c_void) -> &'a QueuedEvent { &*(event as *const QueuedEvent) }
+
+// Error Handling //
+#[no_mangle] pub unsafe extern "system" fn libmyo_error_cstring(details: *mut c_void) -> *const c_char
+{
+    (*(details as *const ErrorDetails)).message.as_ptr()
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_error_kind(details: *mut c_void) -> ResultCode
+{
+    (*(details as *const ErrorDetails)).kind
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_free_error_details(details: *mut c_void)
+{
+    drop(Box::from_raw(details as *mut ErrorDetails));
+}
+
+// Strings //
+#[no_mangle] pub unsafe extern "system" fn libmyo_string_c_str(s: *mut c_void) -> *const c_char
+{
+    (*(s as *const CString)).as_ptr()
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_string_free(s: *mut c_void)
+{
+    drop(Box::from_raw(s as *mut CString));
+}
+#[no_mangle] pub extern "system" fn libmyo_mac_address_to_string(addr: u64) -> *mut c_void
+{
+    let s = (0 .. 6).rev().map(|n| format!("{:02x}", (addr >> (n * 8)) & 0xff)).collect::<Vec<_>>().join("-");
+    Box::into_raw(Box::new(CString::new(s).unwrap())) as *mut c_void
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_string_to_mac_address(s: *const c_char) -> u64
+{
+    let s = CStr::from_ptr(s).to_string_lossy();
+    let parts = s.split('-').collect::<Vec<_>>();
+    if parts.len() != 6 || parts.iter().any(|p| p.len() != 2) { return 0; }
+    parts.iter().try_fold(0u64, |a, p| u8::from_str_radix(p, 16).ok().map(|b| (a << 8) | b as u64)).unwrap_or(0)
+}
+
+// Hub //
+#[no_mangle] pub unsafe extern "system" fn libmyo_init_hub(out_hub: *mut *mut c_void, _application_identifier: *const c_char,
+    out_error: *mut *mut c_void) -> ResultCode
+{
+    if let Some(r) = injected("libmyo_init_hub", out_error) { return r; }
+    if out_hub.is_null() { return fail(out_error, ResultCode::ErrorInvalidArgument, CString::new("out_hub is NULL").unwrap()); }
+    *out_hub = with_state!(s => { let h = s.next_hub; s.next_hub += 1; s.hubs.push(h); h }) as *mut c_void;
+    ResultCode::Success
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_shutdown_hub(hub: *mut c_void, out_error: *mut *mut c_void) -> ResultCode
+{
+    if let Some(r) = injected("libmyo_shutdown_hub", out_error) { return r; }
+    if let Some(r) = check_hub(hub, out_error) { return r; }
+    with_state!(s => s.hubs.retain(|&h| h != hub as usize));
+    ResultCode::Success
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_set_locking_policy(hub: *mut c_void, locking_policy: c_int, out_error: *mut *mut c_void) -> ResultCode
+{
+    if let Some(r) = injected("libmyo_set_locking_policy", out_error) { return r; }
+    if let Some(r) = check_hub(hub, out_error) { return r; }
+    with_state!(s => s.calls.push(FakeCall { kind: CallKind::SetLockingPolicy, myo: 0, argument: locking_policy }));
+    ResultCode::Success
+}
+
+// Myo instances //
+#[no_mangle] pub unsafe extern "system" fn libmyo_vibrate(myo: *mut c_void, vtype: c_int, out_error: *mut *mut c_void) -> ResultCode
+{
+    device_call("libmyo_vibrate", CallKind::Vibrate, myo, vtype, out_error)
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_request_rssi(myo: *mut c_void, out_error: *mut *mut c_void) -> ResultCode
+{
+    device_call("libmyo_request_rssi", CallKind::RequestRSSI, myo, 0, out_error)
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_request_battery_level(myo: *mut c_void, out_error: *mut *mut c_void) -> ResultCode
+{
+    device_call("libmyo_request_battery_level", CallKind::RequestBatteryLevel, myo, 0, out_error)
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_set_stream_emg(myo: *mut c_void, emg: c_int, out_error: *mut *mut c_void) -> ResultCode
+{
+    device_call("libmyo_set_stream_emg", CallKind::SetStreamEMG, myo, emg, out_error)
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_myo_unlock(myo: *mut c_void, utype: c_int, out_error: *mut *mut c_void) -> ResultCode
+{
+    device_call("libmyo_myo_unlock", CallKind::Unlock, myo, utype, out_error)
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_myo_lock(myo: *mut c_void, out_error: *mut *mut c_void) -> ResultCode
+{
+    device_call("libmyo_myo_lock", CallKind::Lock, myo, 0, out_error)
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_myo_notify_user_action(myo: *mut c_void, atype: c_int, out_error: *mut *mut c_void) -> ResultCode
+{
+    device_call("libmyo_myo_notify_user_action", CallKind::NotifyUserAction, myo, atype, out_error)
+}
+
+// Event Handling //
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_type(e: *const c_void) -> u32 { event(e).event.event_type }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_timestamp(e: *const c_void) -> u64 { event(e).event.timestamp }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_myo(e: *const c_void) -> *mut c_void { event(e).event.myo as *mut c_void }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_mac_address(e: *const c_void) -> u64 { event(e).event.mac_address }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_myo_name(e: *const c_void) -> *mut c_void
+{
+    Box::into_raw(Box::new(event(e).name.clone())) as *mut c_void
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_firmware_version(e: *const c_void, c: c_int) -> c_uint
+{
+    event(e).event.firmware_version[c as usize]
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_arm(e: *const c_void) -> c_int { event(e).event.arm }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_x_direction(e: *const c_void) -> c_int { event(e).event.x_direction }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_warmup_state(e: *const c_void) -> c_int { event(e).event.warmup_state }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_warmup_result(e: *const c_void) -> c_int { event(e).event.warmup_result }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_rotation_on_arm(e: *const c_void) -> c_float { event(e).event.rotation_on_arm }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_orientation(e: *const c_void, index: c_int) -> c_float
+{
+    event(e).event.orientation[index as usize]
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_accelerometer(e: *const c_void, index: c_uint) -> c_float
+{
+    event(e).event.accelerometer[index as usize]
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_gyroscope(e: *const c_void, index: c_uint) -> c_float
+{
+    event(e).event.gyroscope[index as usize]
+}
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_pose(e: *const c_void) -> c_int { event(e).event.pose }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_rssi(e: *const c_void) -> i8 { event(e).event.rssi }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_battery_level(e: *const c_void) -> u8 { event(e).event.battery_level }
+#[no_mangle] pub unsafe extern "system" fn libmyo_event_get_emg(e: *const c_void, sensor: c_uint) -> i8
+{
+    event(e).event.emg[sensor as usize]
+}
+
+/// Callback function type to handle events(mirrors `libmyo_handler_t`)
+pub type Handler = extern "system" fn(user_data: *mut c_void, event: *const c_void) -> c_int;
+/// Dispatch all queued events, stopping early when the handler returns `libmyo_handler_stop`.
+/// Sleeps for `duration_ms` if there are no events.
+#[no_mangle] pub unsafe extern "system" fn libmyo_run(hub: *mut c_void, duration_ms: c_uint, handler: Option<Handler>,
+    user_data: *mut c_void, out_error: *mut *mut c_void) -> ResultCode
+{
+    if let Some(r) = injected("libmyo_run", out_error) { return r; }
+    if let Some(r) = check_hub(hub, out_error) { return r; }
+    let handler = match handler
+    {
+        Some(h) => h,
+        None => return fail(out_error, ResultCode::ErrorInvalidArgument, CString::new("handler is NULL").unwrap())
+    };
+    // the lock must not be held while calling the handler, which may call back into libmyo
+    let mut dispatched = false;
+    while let Some(e) = with_state!(s => s.events.pop_front())
+    {
+        dispatched = true;
+        if handler(user_data, &e as *const QueuedEvent as *const c_void) != 0 { break; }
+    }
+    // wait for events like the real hub does
+    if !dispatched { std::thread::sleep(std::time::Duration::from_millis(duration_ms as _)); }
+    ResultCode::Success
+}
+
+// Control Functions //
+/// Reset all state(queued events, recorded calls and injected failures). Live hubs are kept.
+#[no_mangle] pub extern "system" fn fakemyo_reset()
+{
+    with_state!(s => { s.events.clear(); s.calls.clear(); s.failures.clear(); });
+}
+/// Queue an event to be dispatched by the next `libmyo_run`.
+#[no_mangle] pub unsafe extern "system" fn fakemyo_push_event(event: *const FakeEvent, name: *const c_char)
+{
+    let name = if name.is_null() { CString::default() } else { CStr::from_ptr(name).to_owned() };
+    with_state!(s => s.events.push_back(QueuedEvent { event: *event, name }));
+}
+/// Number of events waiting for dispatch
+#[no_mangle] pub extern "system" fn fakemyo_pending_events() -> usize { with_state!(s => s.events.len()) }
+/// Make the next call of `function` fail with `kind` and `message`.
+#[no_mangle] pub unsafe extern "system" fn fakemyo_fail_next(function: *const c_char, kind: ResultCode, message: *const c_char)
+{
+    let function = CStr::from_ptr(function).to_string_lossy().into_owned();
+    let message = CStr::from_ptr(message).to_owned();
+    with_state!(s => s.failures.insert(function, (kind, message)));
+}
+/// Number of hubs initialized and not shut down yet
+#[no_mangle] pub extern "system" fn fakemyo_live_hubs() -> usize { with_state!(s => s.hubs.len()) }
+/// Number of recorded calls
+#[no_mangle] pub extern "system" fn fakemyo_call_count() -> usize { with_state!(s => s.calls.len()) }
+/// Retrieve a recorded call. Returns 0 if `index` is out of range.
+#[no_mangle] pub unsafe extern "system" fn fakemyo_get_call(index: usize, out_call: *mut FakeCall) -> c_int
+{
+    match with_state!(s => s.calls.get(index).cloned())
+    {
+        Some(c) => { *out_call = c; 1 },
+        None => 0
+    }
+}
+
+/// Path to the fake libmyo shared library, next to the running test or example executable.
+pub fn library_path() -> PathBuf
+{
+    let exe = std::env::current_exe().expect("Unable to locate the current executable");
+    let name = libloading::library_filename("fake_libmyo");
+    exe.ancestors().skip(1).take(2).map(|d| d.join(&name)).find(|p| p.exists())
+        .unwrap_or_else(|| panic!("{:?} is not found near {:?}", name, exe))
+}
+
+/// Control handle of a loaded fake libmyo.
+/// The library is shared with `myo`'s dynamically loaded copy as long as both are loaded from the same path.
+pub struct Controller
+{
+    reset: libloading::Symbol<'static, extern "system" fn()>,
+    push_event: libloading::Symbol<'static, unsafe extern "system" fn(*const FakeEvent, *const c_char)>,
+    pending_events: libloading::Symbol<'static, extern "system" fn() -> usize>,
+    live_hubs: libloading::Symbol<'static, extern "system" fn() -> usize>,
+    fail_next: libloading::Symbol<'static, unsafe extern "system" fn(*const c_char, ResultCode, *const c_char)>,
+    call_count: libloading::Symbol<'static, extern "system" fn() -> usize>,
+    get_call: libloading::Symbol<'static, unsafe extern "system" fn(usize, *mut FakeCall) -> c_int>
+}
+impl Controller
+{
+    /// Load the fake libmyo from `library_path()`.
+    pub fn new() -> Self { Self::open(library_path()) }
+    /// Load the fake libmyo from `path`. The library is never unloaded.
+    pub fn open<P: AsRef<std::ffi::OsStr>>(path: P) -> Self
+    {
+        let lib: &'static libloading::Library = Box::leak(Box::new(unsafe { libloading::Library::new(path.as_ref()) }.expect("Unable to load fake libmyo")));
+        unsafe
+        {
+            Controller
+            {
+                reset: lib.get(b"fakemyo_reset\0").unwrap(),
+                push_event: lib.get(b"fakemyo_push_event\0").unwrap(),
+                pending_events: lib.get(b"fakemyo_pending_events\0").unwrap(),
+                live_hubs: lib.get(b"fakemyo_live_hubs\0").unwrap(),
+                fail_next: lib.get(b"fakemyo_fail_next\0").unwrap(),
+                call_count: lib.get(b"fakemyo_call_count\0").unwrap(),
+                get_call: lib.get(b"fakemyo_get_call\0").unwrap()
+            }
+        }
+    }
+
+    /// Reset queued events, recorded calls and injected failures
+    pub fn reset(&self) { (self.reset)() }
+    /// Queue an event
+    pub fn push(&self, event: FakeEvent, name: &str)
+    {
+        let name = CString::new(name).unwrap();
+        unsafe { (self.push_event)(&event, name.as_ptr()) }
+    }
+    /// Number of events waiting for dispatch
+    pub fn pending_events(&self) -> usize { (self.pending_events)() }
+    /// Number of hubs initialized and not shut down yet
+    pub fn live_hubs(&self) -> usize { (self.live_hubs)() }
+    /// Make the next call of `function`(e.g. `"libmyo_run"`) fail
+    pub fn fail_next(&self, function: &str, kind: ResultCode, message: &str)
+    {
+        let (function, message) = (CString::new(function).unwrap(), CString::new(message).unwrap());
+        unsafe { (self.fail_next)(function.as_ptr(), kind, message.as_ptr()) }
+    }
+    /// Recorded calls
+    pub fn calls(&self) -> Vec<FakeCall>
+    {
+        (0 .. (self.call_count)()).filter_map(|n|
+        {
+            let mut c = FakeCall { kind: CallKind::Lock, myo: 0, argument: 0 };
+            if unsafe { (self.get_call)(n, &mut c) } != 0 { Some(c) } else { None }
+        }).collect()
+    }
+}
+impl Default for Controller
+{
+    fn default() -> Self { Self::new() }
+}
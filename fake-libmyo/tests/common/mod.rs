@@ -0,0 +1,23 @@
+//! Shared setup of the fake libmyo tests
+
+use myo::{EventType, HardwareRevision};
+use fake_libmyo::{Controller, FakeEvent};
+use std::sync::{Mutex, MutexGuard, Once};
+
+/// The fake libmyo is process-global: tests take this lock to run one at a time.
+static SERIAL: Mutex<()> = Mutex::new(());
+static LOAD: Once = Once::new();
+pub fn setup() -> (MutexGuard<'static, ()>, Controller)
+{
+    let guard = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    LOAD.call_once(|| myo::load_library(fake_libmyo::library_path()).unwrap());
+    let c = Controller::new();
+    c.reset();
+    (guard, c)
+}
+pub fn event(t: EventType, timestamp: u64) -> FakeEvent
+{
+    let mut e = FakeEvent::new(t as u32, timestamp, 1, 0x010203040506);
+    e.firmware_version = [1, 5, 1970, HardwareRevision::RevD as _];
+    e
+}
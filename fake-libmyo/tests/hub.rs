@@ -0,0 +1,532 @@
+//! Exercise myo's libmyo FFI path against the fake libmyo.
+
+extern crate myo;
+extern crate fake_libmyo;
+
+mod common;
+use common::{setup, event};
+
+use myo::{Event, MyoEvent, EventInfo, EventType, HandlerResult, Pose, Arm, XDirection, WarmupState, WarmupResult, HardwareRevision, VibrationType, UnlockType};
+use fake_libmyo::{Controller, FakeEvent, FakeCall, CallKind, ResultCode};
+use std::time::Duration;
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct Recorder { log: Vec<String>, stop_at: Option<EventType> }
+impl Recorder
+{
+    fn push<E: Event>(&mut self, e: &E, detail: String) -> HandlerResult
+    {
+        self.log.push(format!("{:?}@{} {} {:?} {}", e.event_type(), e.timestamp(), e.mac_address(), e.device_name(), detail));
+        if self.stop_at == Some(e.event_type()) { HandlerResult::Stop } else { HandlerResult::Continue }
+    }
+}
+impl myo::EventListener for Recorder
+{
+    fn on_paired(&mut self, e: myo::PairedEvent) -> HandlerResult { let d = format!("{:?}", e.firmware_versions()); self.push(&e, d) }
+    fn on_unpaired(&mut self, e: myo::UnpairedEvent) -> HandlerResult { self.push(&e, String::new()) }
+    fn on_connected(&mut self, e: myo::ConnectedEvent) -> HandlerResult { let d = format!("{:?}", e.firmware_versions()); self.push(&e, d) }
+    fn on_disconnected(&mut self, e: myo::DisconnectedEvent) -> HandlerResult { self.push(&e, String::new()) }
+    fn on_arm_synced(&mut self, e: myo::ArmSyncedEvent) -> HandlerResult
+    {
+        let d = format!("{:?} {:?} {:?} {}", e.arm(), e.xdirection(), e.warmup_state(), e.rotation_on_arm());
+        self.push(&e, d)
+    }
+    fn on_arm_unsynced(&mut self, e: myo::ArmUnsyncedEvent) -> HandlerResult { self.push(&e, String::new()) }
+    fn on_orientation_data(&mut self, e: myo::OrientationEvent) -> HandlerResult
+    {
+        let d = format!("{:?} {:?} {:?}", e.q_orientation(), e.v_accelerometer(), e.v_gyroscope());
+        self.push(&e, d)
+    }
+    fn on_pose(&mut self, e: myo::PoseEvent) -> HandlerResult { let d = format!("{:?}", e.pose()); self.push(&e, d) }
+    fn on_rssi_value(&mut self, e: myo::RSSIEvent) -> HandlerResult { let d = format!("{}", e.rssi()); self.push(&e, d) }
+    fn on_unlocked(&mut self, e: myo::UnlockedEvent) -> HandlerResult { self.push(&e, String::new()) }
+    fn on_locked(&mut self, e: myo::LockedEvent) -> HandlerResult { self.push(&e, String::new()) }
+    fn on_emg_data(&mut self, e: myo::EMGEvent) -> HandlerResult { let d = format!("{:?}", e.emgs()); self.push(&e, d) }
+    fn on_battery_level(&mut self, e: myo::BatteryLevelEvent) -> HandlerResult { let d = format!("{}", e.battery_level()); self.push(&e, d) }
+    fn on_warmup_completed(&mut self, e: myo::WarmupCompletedEvent) -> HandlerResult { let d = format!("{:?}", e.result()); self.push(&e, d) }
+}
+
+#[test]
+fn dispatches_every_event_type()
+{
+    let (_g, fake) = setup();
+    fake.push(event(EventType::Paired, 1), "my myo");
+    fake.push(event(EventType::Unpaired, 2), "my myo");
+    fake.push(event(EventType::Connected, 3), "my myo");
+    fake.push(event(EventType::Disconnected, 4), "my myo");
+    let mut e = event(EventType::ArmSynced, 5);
+    e.arm = Arm::Left as _; e.x_direction = XDirection::TowardElbow as _; e.warmup_state = WarmupState::Warm as _; e.rotation_on_arm = 0.5;
+    fake.push(e, "my myo");
+    fake.push(event(EventType::ArmUnsynced, 6), "my myo");
+    let mut e = event(EventType::Orientation, 7);
+    e.orientation = [0.0, 0.0, 0.0, 1.0]; e.accelerometer = [0.0, 0.0, 1.0]; e.gyroscope = [1.0, 2.0, 3.0];
+    fake.push(e, "my myo");
+    let mut e = event(EventType::Pose, 8); e.pose = Pose::double_tap as _;
+    fake.push(e, "my myo");
+    let mut e = event(EventType::RSSI, 9); e.rssi = -60;
+    fake.push(e, "my myo");
+    fake.push(event(EventType::Unlocked, 10), "my myo");
+    fake.push(event(EventType::Locked, 11), "my myo");
+    let mut e = event(EventType::EMG, 12); e.emg = [1, -2, 3, -4, 5, -6, 7, -8];
+    fake.push(e, "my myo");
+    let mut e = event(EventType::BatteryLevel, 13); e.battery_level = 87;
+    fake.push(e, "my myo");
+    let mut e = event(EventType::WarmupCompleted, 14); e.warmup_result = WarmupResult::Success as _;
+    fake.push(e, "my myo");
+
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let mut r = Recorder::default();
+    hub.run(10, &mut r).unwrap();
+    let m = "01-02-03-04-05-06 \"my myo\"";
+    assert_eq!(r.log, vec![
+        format!("Paired@1 {} (1, 5, 1970, RevD)", m),
+        format!("Unpaired@2 {} ", m),
+        format!("Connected@3 {} (1, 5, 1970, RevD)", m),
+        format!("Disconnected@4 {} ", m),
+        format!("ArmSynced@5 {} Left TowardElbow Warm 0.5", m),
+        format!("ArmUnsynced@6 {} ", m),
+        format!("Orientation@7 {} Quaternion {{ x: 0.0, y: 0.0, z: 0.0, w: 1.0 }} Vec3 {{ x: 0.0, y: 0.0, z: 1.0 }} Vec3 {{ x: 1.0, y: 2.0, z: 3.0 }}", m),
+        format!("Pose@8 {} double_tap", m),
+        format!("RSSI@9 {} -60", m),
+        format!("Unlocked@10 {} ", m),
+        format!("Locked@11 {} ", m),
+        format!("EMG@12 {} [1, -2, 3, -4, 5, -6, 7, -8]", m),
+        format!("BatteryLevel@13 {} 87", m),
+        format!("WarmupCompleted@14 {} Success", m)
+    ]);
+}
+
+#[test]
+fn handler_stop_ends_run()
+{
+    let (_g, fake) = setup();
+    fake.push(event(EventType::Paired, 1), "");
+    fake.push(event(EventType::Connected, 2), "");
+    fake.push(event(EventType::Locked, 3), "");
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let mut r = Recorder { stop_at: Some(EventType::Connected), .. Recorder::default() };
+    hub.run(10, &mut r).unwrap();
+    assert_eq!(r.log.len(), 2);
+    assert_eq!(fake.pending_events(), 1);
+}
+
+#[test]
+fn injected_errors_are_reported()
+{
+    let (_g, fake) = setup();
+    fake.fail_next("libmyo_init_hub", ResultCode::ErrorInvalidArgument, "bad application identifier");
+    let e = myo::Hub::init("rs.cterm2.rejected").err().unwrap();
+    assert_eq!(e.kind(), myo::ResultCode::ErrorInvalidArgument);
+    assert_eq!(e.to_string(), r#"invalid application identifier "rs.cterm2.rejected""#);
+    assert_eq!(std::error::Error::source(&e).unwrap().to_string(), "bad application identifier");
+    for id in &["bad", "rs.cterm2.-myo", "r-s.cterm2.myo", "rs..myo", "rs.cterm2.myo!"]
+    {
+        assert_eq!(myo::Hub::init(*id).err().unwrap(), myo::Error::InvalidApplicationIdentifier { identifier: id.to_string(), source: None });
+    }
+    myo::Hub::init("").unwrap();
+
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    fake.fail_next("libmyo_run", ResultCode::Runtime, "connection lost");
+    let e = hub.run(10, &mut Recorder::default()).err().unwrap();
+    assert_eq!(e, myo::Error::Runtime("connection lost".to_owned()));
+    assert!(std::error::Error::source(&e).is_none());
+
+    fake.fail_next("libmyo_set_locking_policy", ResultCode::Error, "hub is not a valid hub");
+    assert_eq!(hub.set_locking_policy(myo::LockingPolicy::None).err().unwrap().kind(), myo::ResultCode::Error);
+}
+
+struct Commander<'a>(&'a Controller);
+impl<'a> myo::EventListener for Commander<'a>
+{
+    fn on_paired(&mut self, e: myo::PairedEvent) -> HandlerResult
+    {
+        let d = e.device();
+        d.vibrate(VibrationType::Medium).unwrap();
+        d.set_stream_emg(true).unwrap();
+        d.unlock(UnlockType::Hold).unwrap();
+        d.lock().unwrap();
+        d.request_rssi().unwrap();
+        d.request_battery_level().unwrap();
+        d.notify_user_action().unwrap();
+        self.0.fail_next("libmyo_vibrate", ResultCode::ErrorInvalidArgument, "myo is NULL");
+        HandlerResult::Continue
+    }
+    fn on_connected(&mut self, e: myo::ConnectedEvent) -> HandlerResult
+    {
+        let err = e.device().vibrate(VibrationType::Short).err().unwrap();
+        assert_eq!(err.kind(), myo::ResultCode::ErrorInvalidArgument);
+        HandlerResult::Continue
+    }
+}
+
+#[test]
+fn armband_calls_are_recorded()
+{
+    let (_g, fake) = setup();
+    fake.push(FakeEvent::new(EventType::Paired as _, 1, 7, 1), "");
+    fake.push(FakeEvent::new(EventType::Connected as _, 2, 7, 1), "");
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    hub.set_locking_policy(myo::LockingPolicy::Standard).unwrap();
+    hub.run(10, &mut Commander(&fake)).unwrap();
+    assert_eq!(fake.calls(), vec![
+        FakeCall { kind: CallKind::SetLockingPolicy, myo: 0, argument: myo::LockingPolicy::Standard as _ },
+        FakeCall { kind: CallKind::Vibrate, myo: 7, argument: VibrationType::Medium as _ },
+        FakeCall { kind: CallKind::SetStreamEMG, myo: 7, argument: 1 },
+        FakeCall { kind: CallKind::Unlock, myo: 7, argument: UnlockType::Hold as _ },
+        FakeCall { kind: CallKind::Lock, myo: 7, argument: 0 },
+        FakeCall { kind: CallKind::RequestRSSI, myo: 7, argument: 0 },
+        FakeCall { kind: CallKind::RequestBatteryLevel, myo: 7, argument: 0 },
+        FakeCall { kind: CallKind::NotifyUserAction, myo: 7, argument: 0 }
+    ]);
+}
+
+#[test]
+fn poll_returns_owned_events()
+{
+    let (_g, fake) = setup();
+    fake.push(event(EventType::Paired, 1), "my myo");
+    let mut e = event(EventType::EMG, 2); e.emg = [1, 2, 3, 4, 5, 6, 7, 8];
+    fake.push(e, "my myo");
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let events = hub.poll(10).unwrap();
+    let mac_address = events[0].mac_address();
+    assert_eq!(mac_address.to_string(), "01-02-03-04-05-06");
+    let info = |timestamp| EventInfo { timestamp, mac_address, device_name: "my myo".to_owned() };
+    assert_eq!(events, vec![
+        MyoEvent::Paired(info(1), myo::FirmwareVersion { major: 1, minor: 5, patch: 1970, hardware_revision: HardwareRevision::RevD }),
+        MyoEvent::EMG(info(2), [1, 2, 3, 4, 5, 6, 7, 8])
+    ]);
+    fake.push(event(EventType::Locked, 3), "my myo");
+    assert_eq!(hub.events(10).next().unwrap().unwrap(), MyoEvent::Locked(info(3)));
+}
+
+#[test]
+fn spawned_hub_delivers_events_over_channel()
+{
+    let (_g, fake) = setup();
+    for t in 0 .. 3 { fake.push(event(EventType::Pose, t), "my myo"); }
+    let thread = myo::Hub::init("rs.cterm2.myo.test").unwrap().spawn(5);
+    let timestamps = (0 .. 3).map(|_| thread.receiver().recv().unwrap().timestamp()).collect::<Vec<_>>();
+    assert_eq!(timestamps, vec![0, 1, 2]);
+    let hub = thread.stop().unwrap();
+    fake.fail_next("libmyo_run", ResultCode::Runtime, "connection lost");
+    let thread = hub.spawn(5);
+    assert!(thread.receiver().recv().is_err());
+    assert_eq!(thread.stop().err().unwrap().kind(), myo::ResultCode::Runtime);
+}
+
+#[test]
+fn closure_listener_calls_every_closure_and_stops_on_request()
+{
+    let (_g, fake) = setup();
+    let mut e = event(EventType::Pose, 1); e.pose = Pose::fist as _;
+    fake.push(e, "");
+    fake.push(event(EventType::EMG, 2), "");
+    fake.push(event(EventType::Locked, 3), "");
+    fake.push(event(EventType::EMG, 4), "");
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let (mut poses, mut emgs, mut locked) = (Vec::new(), 0, 0);
+    {
+        let mut listener = myo::Listener::new()
+            .on_pose(|e| poses.push(e.pose()))
+            .on_emg(|_| emgs += 1)
+            .on_locked(|_| HandlerResult::Continue)
+            .on_locked(|_| { locked += 1; HandlerResult::Stop })
+            .on_locked(|_| HandlerResult::Continue);
+        hub.run(10, &mut listener).unwrap();
+    }
+    assert_eq!((poses, emgs, locked), (vec![Pose::fist], 1, 1));
+    assert_eq!(fake.pending_events(), 1);
+}
+
+#[test]
+fn combinators_fan_out_filter_and_map_events()
+{
+    let (_g, fake) = setup();
+    let mut e = event(EventType::Pose, 1); e.pose = Pose::fist as _;
+    fake.push(e, "");
+    let mut e = event(EventType::EMG, 2); e.mac_address = 0x0a0b0c0d0e0f;
+    fake.push(e, "");
+    fake.push(event(EventType::EMG, 3), "");
+    fake.push(event(EventType::Locked, 4), "");
+    fake.push(event(EventType::EMG, 5), "");
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let (mut emgs, mut unlocked, mut all) = (Vec::new(), Vec::new(), Recorder::default());
+    {
+        let emg_listener = myo::Listener::new().on_emg(|e| emgs.push(e.timestamp()));
+        let only_first_device = myo::Filter::new(emg_listener, |e| e.mac_address() == 0x010203040506);
+        let pose_to_unlock = myo::Map::new(myo::Listener::new().on_unlocked(|e| unlocked.push(e.timestamp())), |e| match e
+        {
+            MyoEvent::Pose(info, _) => Some(MyoEvent::Unlocked(info)),
+            _ => None
+        });
+        let stopper = myo::Filter::event_types(myo::Listener::new().on_locked(|_| HandlerResult::Stop), &[EventType::Locked]);
+        let mut listener = (only_first_device, pose_to_unlock, vec![stopper], &mut all);
+        hub.run(10, &mut listener).unwrap();
+    }
+    assert_eq!((emgs, unlocked, all.log.len()), (vec![3], vec![1], 4));
+    assert_eq!(fake.pending_events(), 1);
+}
+
+#[test]
+fn registry_tracks_device_state_and_notifies_changes()
+{
+    use myo::DeviceChange::*;
+    let (_g, fake) = setup();
+    fake.push(event(EventType::Paired, 1), "my myo");
+    fake.push(event(EventType::Connected, 2), "my myo");
+    let mut e = event(EventType::ArmSynced, 3); e.arm = Arm::Left as _; e.x_direction = XDirection::TowardElbow as _; e.rotation_on_arm = 0.5;
+    fake.push(e, "my myo");
+    for t in 4 .. 6 { let mut e = event(EventType::BatteryLevel, t); e.battery_level = 80; fake.push(e, "my myo"); }
+    fake.push(event(EventType::Unlocked, 6), "my myo");
+    fake.push(event(EventType::EMG, 7), "my myo");
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let mut changes = Vec::new();
+    let mut registry = myo::DeviceRegistry::new().on_change(|s, c| changes.push((s.last_update, c)));
+    hub.run(10, &mut registry).unwrap();
+    let state = registry.snapshot().remove(0);
+    assert_eq!((state.name.as_str(), state.paired, state.connected, state.locked, state.battery_level, state.rssi), ("my myo", true, true, Some(false), Some(80), None));
+    let sync = state.arm_sync.unwrap();
+    assert_eq!((sync.arm, sync.x_direction, sync.rotation_on_arm), (Arm::Left, XDirection::TowardElbow, 0.5));
+    assert_eq!(state.firmware.unwrap().hardware_revision, HardwareRevision::RevD);
+    assert_eq!(registry.connected().count(), 1);
+
+    fake.push(event(EventType::Disconnected, 8), "my myo");
+    hub.run(10, &mut registry).unwrap();
+    let state = registry.device(state.mac_address).unwrap();
+    assert_eq!((state.connected, state.arm_sync, state.locked), (false, None, None));
+    drop(registry);
+    assert_eq!(changes, vec![(1, Paired), (1, Renamed), (1, Firmware), (2, Connected), (3, ArmSynced), (4, BatteryLevel), (6, Unlocked),
+        (8, Disconnected), (8, ArmUnsynced)]);
+}
+
+#[test]
+fn registry_takes_armbands_first_seen_through_other_events_as_connected()
+{
+    use myo::DeviceChange::*;
+    let (_g, fake) = setup();
+    fake.push(event(EventType::EMG, 1), "my myo");
+    fake.push(event(EventType::Orientation, 2), "my myo");
+    fake.push(FakeEvent::new(EventType::Disconnected as _, 3, 2, 0x0a0b0c0d0e0f), "other myo");
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let mut changes = Vec::new();
+    let mut registry = myo::DeviceRegistry::new().on_change(|s, c| changes.push((s.last_update, c)));
+    hub.run(10, &mut registry).unwrap();
+    let states: Vec<_> = registry.devices().iter().map(|s| (s.name.clone(), s.paired, s.connected, s.last_update)).collect();
+    assert_eq!(states, vec![("my myo".to_owned(), true, true, 1), ("other myo".to_owned(), true, false, 3)]);
+    drop(registry);
+    assert_eq!(changes, vec![(1, Paired), (1, Connected), (1, Renamed), (3, Paired), (3, Renamed)]);
+}
+
+#[test]
+fn armband_handles_follow_reconnects_and_report_gone_devices()
+{
+    let (_g, fake) = setup();
+    fake.push(event(EventType::Paired, 1), "");
+    fake.push(event(EventType::Connected, 2), "");
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    hub.poll(10).unwrap();
+    let armband = hub.armbands().remove(0);
+    assert!(armband.is_paired() && armband.is_connected());
+    armband.vibrate(VibrationType::Short).unwrap();
+
+    fake.push(event(EventType::Disconnected, 3), "");
+    hub.poll(10).unwrap();
+    assert!(armband.is_paired() && !armband.is_connected());
+    let e = armband.vibrate(VibrationType::Short).unwrap_err();
+    assert_eq!(e, myo::Error::DeviceGone { mac_address: armband.mac_address(), reason: "is disconnected".to_owned() });
+    assert_eq!(e.to_string(), "armband 01-02-03-04-05-06 is disconnected");
+
+    let mut e = event(EventType::Connected, 4); e.myo = 2;
+    fake.push(e, "");
+    hub.poll(10).unwrap();
+    armband.vibrate(VibrationType::Long).unwrap();
+
+    let mut e = event(EventType::Unpaired, 5); e.myo = 2;
+    fake.push(e, "");
+    hub.poll(10).unwrap();
+    assert!(!armband.is_paired() && hub.armbands().is_empty());
+    let e = armband.lock().unwrap_err();
+    assert_eq!(e.to_string(), "armband 01-02-03-04-05-06 has been unpaired");
+    assert_eq!(fake.calls(), vec![
+        FakeCall { kind: CallKind::Vibrate, myo: 1, argument: VibrationType::Short as _ },
+        FakeCall { kind: CallKind::Vibrate, myo: 2, argument: VibrationType::Long as _ }
+    ]);
+}
+
+#[test]
+fn armband_handles_and_registry_agree_on_armbands_first_seen_through_other_events()
+{
+    let (_g, fake) = setup();
+    fake.push(event(EventType::EMG, 1), "");
+    fake.push(FakeEvent::new(EventType::Disconnected as _, 2, 2, 0x0a0b0c0d0e0f), "");
+    fake.push(FakeEvent::new(EventType::Unpaired as _, 3, 3, 0x0b0c0d0e0f10), "");
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let mut registry = myo::DeviceRegistry::new();
+    hub.run(10, &mut registry).unwrap();
+    for s in registry.devices()
+    {
+        let armband = hub.armband(s.mac_address);
+        assert_eq!((armband.is_paired(), armband.is_connected()), (s.paired, s.connected), "{}", s.mac_address);
+    }
+    let states: Vec<_> = registry.devices().iter().map(|s| (s.paired, s.connected)).collect();
+    assert_eq!(states, vec![(true, true), (true, false), (false, false)]);
+    hub.armbands()[0].vibrate(VibrationType::Short).unwrap();
+}
+
+#[test]
+fn command_queue_applies_commands_between_run_slices()
+{
+    let (_g, fake) = setup();
+    fake.push(event(EventType::Connected, 1), "");
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let queue = hub.command_queue();
+    let thread = hub.spawn(5);
+    let mac = thread.receiver().recv().unwrap().mac_address();
+    let armband = queue.armband(mac);
+    let worker = { let armband = armband.clone(); std::thread::spawn(move || armband.vibrate(VibrationType::Medium).wait()) };
+    worker.join().unwrap().unwrap();
+    assert_eq!(fake.calls(), vec![FakeCall { kind: CallKind::Vibrate, myo: 1, argument: VibrationType::Medium as _ }]);
+
+    let hub = thread.stop().unwrap();
+    fake.push(event(EventType::Disconnected, 2), "");
+    hub.poll(10).unwrap();
+    let pending = armband.lock();
+    assert!(pending.try_result().is_none());
+    assert_eq!(pending.wait_timeout(Duration::from_millis(5)), Err(myo::Error::Timeout(Duration::from_millis(5))));
+    assert_eq!(hub.apply_commands(), 1);
+    assert_eq!(pending.try_result().unwrap().unwrap_err().to_string(), "armband 01-02-03-04-05-06 is disconnected");
+    let pending = armband.lock();
+    drop(hub);
+    assert!(pending.wait().is_err());
+    assert!(queue.send(mac, myo::Command::Lock).wait().is_err());
+}
+
+#[test]
+fn shutdown_reports_errors_and_flushes_attached_sinks()
+{
+    let (_g, fake) = setup();
+    let path = std::env::temp_dir().join(format!("myo-shutdown-{}.myor", std::process::id()));
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    hub.attach(myo::Recorder::create(&path, "rs.cterm2.myo.test", &[]).unwrap());
+    for t in 0 .. 3 { fake.push(event(EventType::Pose, t), ""); }
+    let events = hub.poll(10).unwrap();
+    assert_eq!(events.len(), 3);
+    hub.shutdown().unwrap();
+    let recorded = myo::RecordingReader::open(&path).unwrap().collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(recorded, events);
+    std::fs::remove_file(&path).unwrap();
+
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    fake.fail_next("libmyo_shutdown_hub", ResultCode::Runtime, "hub is busy");
+    assert_eq!(hub.shutdown(), Err(myo::Error::Runtime("hub is busy".to_owned())));
+    // dropping only logs the failure
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    fake.fail_next("libmyo_shutdown_hub", ResultCode::Runtime, "hub is busy");
+    drop(hub);
+
+    let thread = myo::Hub::init("rs.cterm2.myo.test").unwrap().spawn(5);
+    fake.push(event(EventType::Pose, 4), "");
+    assert_eq!(thread.receiver().recv().unwrap().timestamp(), 4);
+    thread.shutdown().unwrap();
+}
+
+#[test]
+fn dropping_a_hub_thread_shuts_the_hub_down()
+{
+    let (_g, fake) = setup();
+    let path = std::env::temp_dir().join(format!("myo-hub-thread-{}.myor", std::process::id()));
+    let live = fake.live_hubs();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    hub.attach(myo::Recorder::create(&path, "rs.cterm2.myo.test", &[]).unwrap());
+    let thread = hub.spawn(5);
+    assert_eq!(fake.live_hubs(), live + 1);
+    fake.push(event(EventType::Pose, 1), "");
+    assert_eq!(thread.receiver().recv().unwrap().timestamp(), 1);
+    // idle: no event makes the thread notice a dropped receiver
+    drop(thread);
+    assert_eq!(fake.live_hubs(), live);
+    let recorded = myo::RecordingReader::open(&path).unwrap().collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(recorded.iter().map(MyoEvent::timestamp).collect::<Vec<_>>(), vec![1]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Sink collecting the timestamps of pose events
+struct PoseLog(Arc<Mutex<Vec<u64>>>, Arc<Mutex<usize>>);
+impl myo::EventListener for PoseLog
+{
+    fn on_pose(&mut self, e: myo::PoseEvent) -> HandlerResult { self.0.lock().unwrap().push(e.timestamp()); HandlerResult::Continue }
+}
+impl myo::EventSink for PoseLog
+{
+    fn flush(&mut self) -> std::io::Result<()> { *self.1.lock().unwrap() += 1; Ok(()) }
+}
+
+#[test]
+fn sinks_attached_during_a_run_start_with_the_next_event()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let (first, second, flushes) = (Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(0)));
+    hub.attach(PoseLog(first.clone(), flushes.clone()));
+    for t in 0 .. 3 { fake.push(event(EventType::Pose, t), ""); }
+    hub.run(10, &mut myo::Listener::new().on_pose(|e| if e.timestamp() == 0 { hub.attach(PoseLog(second.clone(), flushes.clone())); })).unwrap();
+    assert_eq!(*first.lock().unwrap(), vec![0, 1, 2]);
+    assert_eq!(*second.lock().unwrap(), vec![1, 2]);
+    // sinks attached after the last run are flushed as well
+    hub.attach(PoseLog(Arc::new(Mutex::new(Vec::new())), flushes.clone()));
+    hub.shutdown().unwrap();
+    assert_eq!(*flushes.lock().unwrap(), 3);
+}
+
+/// Backend failing with a panic
+struct PanickingBackend;
+impl myo::Backend for PanickingBackend
+{
+    fn set_locking_policy(&self, _: myo::LockingPolicy) -> myo::Result<()> { Ok(()) }
+    fn run(&self, _: u32, _: &mut myo::BackendHandler) -> myo::Result<()> { panic!("backend failed") }
+}
+
+#[test]
+fn hub_thread_panics_are_resumed_by_stop_and_only_logged_on_drop()
+{
+    // the channel closes once the thread has panicked
+    let thread = myo::Hub::with_backend(PanickingBackend).spawn(5);
+    assert!(thread.receiver().recv().is_err());
+    drop(thread);
+    let thread = myo::Hub::with_backend(PanickingBackend).spawn(5);
+    assert!(thread.receiver().recv().is_err());
+    let r = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| thread.stop().is_ok()));
+    assert_eq!(*r.unwrap_err().downcast::<&str>().unwrap(), "backend failed");
+}
+
+#[test]
+fn listener_panics_stop_the_run_and_unknown_values_fall_back()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    for t in 0 .. 3 { fake.push(event(EventType::Pose, t), ""); }
+    let r = std::panic::catch_unwind(std::panic::AssertUnwindSafe(||
+    {
+        hub.run(10, &mut myo::Listener::new().on_pose(|e| if e.timestamp() == 1 { panic!("listener failed") }))
+    }));
+    assert_eq!(*r.unwrap_err().downcast::<&str>().unwrap(), "listener failed");
+    assert_eq!(fake.pending_events(), 1);
+    assert_eq!(hub.poll(10).unwrap().iter().map(MyoEvent::timestamp).collect::<Vec<_>>(), vec![2]);
+
+    let mut e = FakeEvent::new(99, 3, 1, 0x010203040506);
+    fake.push(e, "");
+    e = event(EventType::Pose, 4); e.pose = 42; fake.push(e, "");
+    e = event(EventType::ArmSynced, 5); e.arm = 7; e.x_direction = -1; e.warmup_state = 3; fake.push(e, "");
+    e = event(EventType::WarmupCompleted, 6); e.warmup_result = 9; fake.push(e, "");
+    e = event(EventType::Connected, 7); e.firmware_version[3] = 5; fake.push(e, "");
+    let events = hub.poll(10).unwrap();
+    let info = |timestamp| EventInfo { timestamp, mac_address: events[0].mac_address(), device_name: String::new() };
+    assert_eq!(events, vec![
+        MyoEvent::Pose(info(4), Pose::unknown),
+        MyoEvent::ArmSynced(info(5), myo::ArmSync { arm: Arm::Unknown, x_direction: XDirection::Unknown, warmup_state: WarmupState::Unknown, rotation_on_arm: 0.0 }),
+        MyoEvent::WarmupCompleted(info(6), WarmupResult::Unknown),
+        MyoEvent::Connected(info(7), myo::FirmwareVersion { major: 1, minor: 5, patch: 1970, hardware_revision: HardwareRevision::Unknown })
+    ]);
+}
@@ -0,0 +1,234 @@
+//! Processing of orientation events.
+
+extern crate myo;
+extern crate fake_libmyo;
+
+mod common;
+use common::{setup, event};
+
+use myo::{Quaternion, Vec3, EventType, Arm, XDirection, WarmupState, ArmSync, ForearmTracker, Calibrator, Pose,
+    Madgwick, Mahony, SensorFusion, GyroBiasConfig, GyroBiasTracker};
+use std::f32::consts::{PI, FRAC_PI_2};
+
+fn assert_close(a: f32, b: f32) { assert!((a - b).abs() < 1e-5, "{} != {}", a, b); }
+fn assert_rotation_close(a: Quaternion, b: Quaternion) { assert_close(a.dot(b).abs(), 1.0); }
+/// Angle of the rotation between two orientations
+fn angle_between(a: Quaternion, b: Quaternion) -> f32 { 2.0 * a.dot(b).abs().min(1.0).acos() }
+/// Accelerometer reading(g) of an armband at rest
+fn gravity(q: Quaternion) -> Vec3 { q.conjugate().rotate(Vec3::new(0.0, 0.0, 1.0)) }
+
+#[test]
+fn orientation_events_provide_quaternions_and_vectors()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let mut e = event(EventType::Orientation, 1);
+    e.orientation = [0.0, 0.0, 0.70710677, 0.70710677]; e.accelerometer = [0.0, 0.0, 1.0]; e.gyroscope = [1.0, 2.0, 3.0];
+    fake.push(e, "");
+    let mut seen = None;
+    hub.run(10, &mut myo::Listener::new().on_orientation(|e| seen = Some((e.q_orientation(), e.v_accelerometer(), e.v_gyroscope())))).unwrap();
+    let (q, a, g) = seen.unwrap();
+    assert_close(q.yaw(), FRAC_PI_2);
+    assert_eq!(a, Vec3::new(0.0, 0.0, 1.0));
+    assert_eq!(g, Vec3::new(1.0, 2.0, 3.0));
+}
+
+#[test]
+fn forearm_orientation_is_the_same_for_either_arm_and_direction()
+{
+    let (x, z) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let (roll, pitch, yaw) = (0.3, 0.4, -0.5);
+    let sync = |arm, x_direction, rotation_on_arm| ArmSync { arm, x_direction, warmup_state: WarmupState::Warm, rotation_on_arm };
+    for &(arm, sign) in &[(Arm::Right, 1.0), (Arm::Left, -1.0)]
+    {
+        // a left arm makes the mirrored movement
+        let forearm = Quaternion::from_euler_angles(sign * roll, pitch, sign * yaw);
+        for &x_direction in &[XDirection::TowardWrist, XDirection::TowardElbow]
+        {
+            let flip = if x_direction == XDirection::TowardElbow { Quaternion::from_axis_angle(z, -PI) } else { Quaternion::identity() };
+            for &rotation_on_arm in &[0.0, 1.0, -2.5]
+            {
+                let armband = forearm * flip * Quaternion::from_axis_angle(x, rotation_on_arm);
+                let (r, p, w) = sync(arm, x_direction, rotation_on_arm).forearm_orientation(armband).euler_angles();
+                assert_close(r, roll); assert_close(p, pitch); assert_close(w, yaw);
+            }
+        }
+    }
+}
+
+#[test]
+fn forearm_pitch_is_positive_with_the_hand_lowered()
+{
+    let sync = ArmSync { arm: Arm::Right, x_direction: XDirection::TowardWrist, warmup_state: WarmupState::Warm, rotation_on_arm: 0.0 };
+    // the forearm tilted so that its +x axis(toward the wrist) points below the horizon
+    let lowered = Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), 0.5);
+    let forearm = sync.forearm_orientation(lowered);
+    assert!(forearm.rotate(Vec3::new(1.0, 0.0, 0.0)).z < 0.0);
+    assert_close(forearm.pitch(), 0.5);
+}
+
+#[test]
+fn forearm_tracker_follows_sync_events()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let armband = Quaternion::from_euler_angles(0.0, 0.25, 0.5);
+    let orientation = |t| { let mut e = event(EventType::Orientation, t); e.orientation = armband.into(); e };
+    fake.push(orientation(1), "");
+    let mut e = event(EventType::ArmSynced, 2);
+    e.arm = Arm::Right as _; e.x_direction = XDirection::TowardWrist as _; e.rotation_on_arm = 0.0;
+    fake.push(e, "");
+    fake.push(orientation(3), "");
+    let mut e = event(EventType::ArmSynced, 4);
+    e.arm = Arm::Left as _; e.x_direction = XDirection::TowardElbow as _; e.rotation_on_arm = 0.0;
+    fake.push(e, "");
+    fake.push(orientation(5), "");
+    fake.push(event(EventType::ArmUnsynced, 6), "");
+    fake.push(orientation(7), "");
+
+    let (mac_address, mut seen) = (myo::MACAddress::new(0x010203040506).unwrap(), Vec::new());
+    {
+        let mut tracker = ForearmTracker::new().on_update(|_, o| seen.push(*o));
+        hub.run(10, &mut tracker).unwrap();
+        assert_eq!(tracker.orientation(mac_address).unwrap().timestamp, 7);
+        assert!(tracker.sync(mac_address).is_none());
+    }
+    assert_eq!(seen.iter().map(|o| (o.timestamp, o.arm)).collect::<Vec<_>>(),
+        vec![(1, Arm::Unknown), (3, Arm::Right), (5, Arm::Left), (7, Arm::Unknown)]);
+    assert_rotation_close(seen[0].orientation, armband);
+    assert_close(seen[1].pitch, 0.25); assert_close(seen[1].yaw, 0.5);
+    // turned around on the other arm: the forearm points the opposite way, mirrored
+    assert_close(seen[2].pitch, -0.25); assert_close(seen[2].yaw, -(0.5 - PI));
+    assert_rotation_close(seen[3].orientation, armband);
+}
+
+#[test]
+fn calibrator_reports_orientations_relative_to_the_captured_reference()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let orientation = |t, q: Quaternion| { let mut e = event(EventType::Orientation, t); e.orientation = q.into(); fake.push(e, ""); };
+    let pose = |t, p: Pose| { let mut e = event(EventType::Pose, t); e.pose = p as _; fake.push(e, ""); };
+    let neutral = Quaternion::from_euler_angles(0.1, -0.2, 1.0);
+    orientation(1, neutral);
+    pose(2, Pose::double_tap);
+    orientation(3, neutral);
+    orientation(4, neutral * Quaternion::from_euler_angles(0.0, 0.3, 0.4));
+    // drift: the device yaw has slipped
+    let drifted = Quaternion::from_euler_angles(0.0, 0.0, 0.5) * neutral;
+    orientation(5, drifted);
+    pose(6, Pose::fist);
+    orientation(7, drifted);
+
+    let (mac_address, mut seen) = (myo::MACAddress::new(0x010203040506).unwrap(), Vec::new());
+    {
+        let mut calibrator = Calibrator::new().capture_on(Pose::double_tap).recenter_yaw_on(Pose::fist).on_update(|_, o| seen.push(*o));
+        hub.run(10, &mut calibrator).unwrap();
+        assert_rotation_close(calibrator.reference(mac_address).unwrap(), drifted);
+        assert_rotation_close(calibrator.relative(mac_address).unwrap(), Quaternion::identity());
+
+        calibrator.clear(mac_address);
+        assert_rotation_close(calibrator.relative(mac_address).unwrap(), drifted);
+        // re-centering without a reference keeps the tilt
+        assert!(calibrator.recenter_yaw(mac_address));
+        assert_close(calibrator.relative(mac_address).unwrap().yaw(), 0.0);
+        assert_close(calibrator.relative(mac_address).unwrap().pitch(), drifted.pitch());
+        calibrator.set_reference(mac_address, drifted);
+        assert!(calibrator.capture(mac_address));
+        assert!(!calibrator.capture(myo::MACAddress::new(1).unwrap()));
+    }
+    assert_eq!(seen.iter().map(|o| (o.timestamp, o.calibrated)).collect::<Vec<_>>(), vec![(1, false), (3, true), (4, true), (5, true), (7, true)]);
+    assert_rotation_close(seen[0].orientation, neutral);
+    assert_rotation_close(seen[1].orientation, Quaternion::identity());
+    let (r, p, w) = seen[2].orientation.euler_angles();
+    assert_close(r, 0.0); assert_close(p, 0.3); assert_close(w, 0.4);
+    // the drift shows up as a 0.5rad turn until the yaw is re-centered
+    assert_close(seen[3].orientation.w.abs(), (0.25f32).cos());
+    assert_rotation_close(seen[4].orientation, Quaternion::identity());
+}
+
+#[test]
+fn calibrator_keeps_the_reference_when_capturing_without_an_orientation()
+{
+    let mac_address = myo::MACAddress::new(0x010203040506).unwrap();
+    let reference = Quaternion::from_euler_angles(0.1, -0.2, 1.0);
+    let mut calibrator = Calibrator::new();
+    calibrator.set_reference(mac_address, reference);
+    assert!(!calibrator.capture(mac_address));
+    assert_rotation_close(calibrator.reference(mac_address).unwrap(), reference);
+}
+
+#[test]
+fn ahrs_filters_follow_the_armband_orientation()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    // turning at a constant rate, sampled at 50Hz like the armband
+    let (start, rate) = (Quaternion::from_euler_angles(0.2, -0.1, 0.5), Vec3::new(0.5, -0.3, 0.8));
+    for n in 0 .. 100
+    {
+        let t = n as f32 * 0.02;
+        let q = start * Quaternion::from_axis_angle(rate.normalize(), rate.length() * t);
+        let mut e = event(EventType::Orientation, 1000 + n * 20_000);
+        e.orientation = q.into(); e.accelerometer = gravity(q).into();
+        e.gyroscope = [rate.x.to_degrees(), rate.y.to_degrees(), rate.z.to_degrees()];
+        fake.push(e, "");
+    }
+
+    let (mut madgwick, mut mahony) = (Vec::new(), Vec::new());
+    {
+        let mut fusion = (
+            SensorFusion::new(Madgwick::new(0.05)).on_update(|_, o| madgwick.push(angle_between(o.orientation, o.device))),
+            SensorFusion::new(Mahony::default()).on_update(|_, o| mahony.push(angle_between(o.orientation, o.device))));
+        hub.run(10, &mut fusion).unwrap();
+        let mac_address = myo::MACAddress::new(0x010203040506).unwrap();
+        assert_eq!(fusion.0.filter(mac_address).unwrap().beta, 0.05);
+        assert!(fusion.1.orientation(mac_address).is_some());
+    }
+    assert_eq!((madgwick.len(), mahony.len()), (100, 100));
+    assert_eq!(madgwick[0], 0.0);
+    assert!(madgwick.iter().chain(&mahony).all(|&a| a < 0.02), "{:?} {:?}", madgwick, mahony);
+}
+
+#[test]
+fn gyro_bias_is_learned_while_stationary()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    let bias = Vec3::new(1.5, -0.5, 0.8);
+    let mut t = 0;
+    let mut push = |accelerometer: Vec3, gyroscope: Vec3|
+    {
+        t += 20_000;
+        let mut e = event(EventType::Orientation, t);
+        e.accelerometer = accelerometer.into(); e.gyroscope = gyroscope.into();
+        fake.push(e, "");
+    };
+    // at rest with some sensor noise, moving, and at rest again
+    let noise = |n: usize| if n % 2 == 0 { 0.2 } else { -0.2 };
+    for n in 0 .. 100 { push(Vec3::new(0.0, 0.0, 1.0 + noise(n) * 0.01), bias + Vec3::new(noise(n), 0.0, 0.0)); }
+    for n in 0 .. 50 { let s = (n as f32 * 0.3).sin(); push(Vec3::new(s * 0.5, 0.0, 1.0), bias + Vec3::new(0.0, 90.0 * s, 0.0)); }
+    for n in 0 .. 100 { push(Vec3::new(0.0, 0.0, 1.0), bias + Vec3::new(0.0, 0.0, noise(n))); }
+    fake.push(event(EventType::Disconnected, 6_000_000), "");
+
+    let mac_address = myo::MACAddress::new(0x010203040506).unwrap();
+    let mut seen = Vec::new();
+    {
+        let config = GyroBiasConfig { learning_rate: 0.1, ..GyroBiasConfig::default() };
+        let mut tracker = GyroBiasTracker::new(config).on_update(|_, g| seen.push(*g));
+        hub.run(10, &mut tracker).unwrap();
+        // the samples are forgotten on disconnection, but not the bias
+        assert!(!tracker.is_stationary(mac_address));
+        assert!((tracker.estimator(mac_address).unwrap().bias() - bias).length() < 0.05);
+    }
+    assert_eq!(seen.len(), 250);
+    // the window has to fill up first
+    assert!(seen[.. 24].iter().all(|g| !g.stationary && g.bias == Vec3::new(0.0, 0.0, 0.0)));
+    assert!(seen[24 .. 100].iter().all(|g| g.stationary));
+    assert!((seen[99].bias - bias).length() < 0.05, "{:?}", seen[99].bias);
+    assert!(seen[99].gyroscope.length() < 0.3);
+    // the bias is kept while moving
+    assert!(seen[110 .. 150].iter().all(|g| !g.stationary && g.bias == seen[100].bias));
+    assert!(seen[249].stationary);
+    assert!(seen[249].gyroscope.length() < 0.3);
+}
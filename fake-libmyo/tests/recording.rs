@@ -0,0 +1,295 @@
+//! Session recordings, replays and exports of events from the fake libmyo.
+
+extern crate myo;
+extern crate fake_libmyo;
+extern crate serde_json;
+
+mod common;
+use common::{setup, event};
+
+use myo::{EventType, MyoEvent, Pose, Arm, XDirection, WarmupState, WarmupResult, Recorder, RecordingReader, RecordedDevice,
+    ReplayBackend, ReplaySpeed, HandlerResult, VibrationType, Event, CsvWriter, CsvConfig, TimestampUnit,
+    JsonlWriter, JsonlReader};
+use fake_libmyo::Controller;
+use std::time::{Duration, Instant};
+
+/// Push one event of every type
+fn push_all_types(fake: &Controller)
+{
+    let types = [EventType::Paired, EventType::Connected, EventType::ArmSynced, EventType::Orientation, EventType::Pose,
+        EventType::RSSI, EventType::Unlocked, EventType::EMG, EventType::BatteryLevel, EventType::WarmupCompleted,
+        EventType::Locked, EventType::ArmUnsynced, EventType::Disconnected, EventType::Unpaired];
+    for (n, &t) in types.iter().enumerate()
+    {
+        let mut e = event(t, n as u64 * 1000);
+        e.arm = Arm::Left as _; e.x_direction = XDirection::TowardWrist as _; e.warmup_state = WarmupState::Cold as _;
+        e.rotation_on_arm = -1.25; e.orientation = [0.5, -0.5, 0.25, 0.75]; e.accelerometer = [0.0, 1.0, -1.0];
+        e.gyroscope = [10.0, 20.0, 30.0]; e.pose = Pose::wave_in as _; e.rssi = -60; e.battery_level = 42;
+        e.emg = [-128, -1, 0, 1, 2, 3, 4, 127]; e.warmup_result = WarmupResult::Success as _;
+        fake.push(e, "my myo");
+    }
+}
+
+#[test]
+fn recordings_round_trip_every_event_type()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    push_all_types(&fake);
+    let events = hub.poll(10).unwrap();
+    assert_eq!(events.len(), 14);
+
+    let mut recorder = Recorder::new(Vec::new(), "rs.cterm2.myo.test", &[]).unwrap();
+    push_all_types(&fake);
+    hub.run(10, &mut recorder).unwrap();
+    let data = recorder.finish().unwrap();
+
+    let mut reader = RecordingReader::new(&data[..]).unwrap();
+    assert_eq!(reader.header().application_identifier, "rs.cterm2.myo.test");
+    assert!(!reader.header().recorder_version.is_empty());
+    assert!(reader.header().devices.is_empty());
+    let read = reader.by_ref().collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(read, events);
+    assert_eq!(reader.devices(), &[RecordedDevice { mac_address: events[0].mac_address(), name: "my myo".to_owned(), firmware: match events[0]
+    {
+        MyoEvent::Paired(_, f) => Some(f),
+        _ => unreachable!()
+    } }]);
+
+    // a record torn by a crash is dropped
+    let torn = RecordingReader::new(&data[.. data.len() - 3]).unwrap().collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(torn, &events[.. 13]);
+    assert!(RecordingReader::new(&b"MYOX"[..]).is_err());
+}
+
+#[test]
+fn recordings_can_be_appended_after_a_crash()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    push_all_types(&fake);
+    let events = hub.poll(10).unwrap();
+    let path = std::env::temp_dir().join(format!("myo-recording-{}.myor", std::process::id()));
+
+    let devices = [RecordedDevice { mac_address: events[0].mac_address(), name: "known".to_owned(), firmware: None }];
+    let mut recorder = Recorder::create(&path, "rs.cterm2.myo.test", &devices).unwrap();
+    for e in &events[.. 7] { recorder.write_event(e).unwrap(); }
+    drop(recorder);
+    // simulate a crash in the middle of a record
+    let mut data = std::fs::read(&path).unwrap();
+    data.extend_from_slice(&[7, 30, 0, 1, 2]);
+    std::fs::write(&path, &data).unwrap();
+
+    let mut recorder = Recorder::append(&path).unwrap();
+    for e in &events[7 ..] { recorder.write_event(e).unwrap(); }
+    recorder.finish().unwrap();
+
+    let reader = RecordingReader::open(&path).unwrap();
+    assert_eq!(reader.header().devices, devices);
+    assert_eq!(reader.collect::<std::io::Result<Vec<_>>>().unwrap(), events);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn recordings_reject_values_beyond_their_field_sizes()
+{
+    let (_g, fake) = setup();
+    fake.push(event(EventType::Paired, 1), "");
+    let mac_address = myo::Hub::init("rs.cterm2.myo.test").unwrap().poll(10).unwrap()[0].mac_address();
+
+    let invalid_input = |r: std::io::Result<_>| r.err().map(|e| e.kind()) == Some(std::io::ErrorKind::InvalidInput);
+    let long = "x".repeat(u16::MAX as usize + 1);
+    assert!(invalid_input(Recorder::new(Vec::new(), &long, &[]).map(|_| ())));
+    let devices = vec![RecordedDevice { mac_address, name: String::new(), firmware: None }; u16::MAX as usize + 1];
+    assert!(invalid_input(Recorder::new(Vec::new(), "rs.cterm2.myo.test", &devices).map(|_| ())));
+
+    let mut recorder = Recorder::new(Vec::new(), "rs.cterm2.myo.test", &[]).unwrap();
+    let info = myo::EventInfo { timestamp: 1, mac_address, device_name: long };
+    assert!(invalid_input(recorder.write_event(&MyoEvent::Locked(info))));
+    // nothing was written for the rejected event
+    assert!(RecordingReader::new(&recorder.finish().unwrap()[..]).unwrap().next().is_none());
+}
+
+#[test]
+fn replay_backend_feeds_recorded_events_to_listeners()
+{
+    let (_g, fake) = setup();
+    push_all_types(&fake);
+    let events = myo::Hub::init("rs.cterm2.myo.test").unwrap().poll(10).unwrap();
+    let mac_address = events[0].mac_address();
+
+    // original timing: the events span 13ms
+    let hub = myo::Hub::with_backend(ReplayBackend::new(events.clone()));
+    let (started, mut replayed) = (Instant::now(), Vec::new());
+    while !hub.backend().is_finished() { replayed.extend(hub.poll(5).unwrap()); }
+    assert!(started.elapsed() >= Duration::from_millis(13));
+    assert_eq!(replayed, events);
+
+    // filtering, seeking and commands
+    let hub = myo::Hub::with_backend(ReplayBackend::new(events.clone()).speed(ReplaySpeed::AsFastAsPossible)
+        .devices(&[mac_address]).event_types(&[EventType::Connected, EventType::Pose, EventType::EMG]));
+    hub.backend().seek(2000);
+    let seen = std::cell::RefCell::new(Vec::new());
+    {
+        let mut listener = myo::Listener::new()
+            .on_pose(|e| { seen.borrow_mut().push(e.timestamp()); e.device().vibrate(VibrationType::Short).unwrap(); })
+            .on_emg(|e| seen.borrow_mut().push(e.timestamp()));
+        while !hub.backend().is_finished() { hub.run(10, &mut listener).unwrap(); }
+    }
+    assert_eq!(seen.into_inner(), vec![4000, 7000]);
+    assert_eq!(hub.backend().executed_commands(), vec![(mac_address, myo::Command::Vibrate(VibrationType::Short))]);
+
+    // unsorted input is replayed in timestamp order
+    let mut shuffled = events.clone();
+    shuffled.reverse();
+    let hub = myo::Hub::with_backend(ReplayBackend::new(shuffled).speed(ReplaySpeed::AsFastAsPossible));
+    hub.backend().seek(5000);
+    assert_eq!(hub.backend().position(), Some(5000));
+    assert_eq!(hub.poll(10).unwrap(), &events[5 ..]);
+
+    // looping
+    let hub = myo::Hub::with_backend(ReplayBackend::new(events).speed(ReplaySpeed::Scaled(100.0)).looping(true)
+        .event_types(&[EventType::Connected, EventType::Pose]));
+    let mut poses = 0;
+    hub.run(1000, &mut myo::Listener::new().on_pose(|_| { poses += 1; if poses == 3 { HandlerResult::Stop } else { HandlerResult::Continue } })).unwrap();
+    assert_eq!(poses, 3);
+    assert!(!hub.backend().is_finished());
+    hub.armband(mac_address).lock().unwrap();
+}
+
+#[test]
+fn replayed_events_report_zero_for_sensors_out_of_range()
+{
+    let (_g, fake) = setup();
+    push_all_types(&fake);
+    let events = myo::Hub::init("rs.cterm2.myo.test").unwrap().poll(10).unwrap();
+    let hub = myo::Hub::with_backend(ReplayBackend::new(events).speed(ReplaySpeed::AsFastAsPossible).event_types(&[EventType::EMG, EventType::Orientation]));
+    let seen = std::cell::RefCell::new(Vec::new());
+    hub.run(10, &mut myo::Listener::new()
+        .on_emg(|e| seen.borrow_mut().push((e.emg(7) as f32, e.emg(8) as f32)))
+        .on_orientation(|e| seen.borrow_mut().push((e.raw().accelerometer(2), e.raw().gyroscope(3))))).unwrap();
+    assert_eq!(seen.into_inner(), vec![(-1.0, 0.0), (127.0, 0.0)]);
+}
+
+#[test]
+fn csv_export_from_listener_and_recording()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    push_all_types(&fake);
+    let mut recorder = Recorder::new(Vec::new(), "rs.cterm2.myo.test", &[]).unwrap();
+    let mut csv = CsvWriter::new(CsvConfig::default()).unwrap().emg(Vec::new()).imu(Vec::new());
+    hub.run(10, &mut (&mut recorder, &mut csv)).unwrap();
+    let (emg, imu) = csv.finish().unwrap();
+    assert_eq!(String::from_utf8(emg.unwrap()).unwrap(),
+        "timestamp,mac,emg0,emg1,emg2,emg3,emg4,emg5,emg6,emg7\n7000,01-02-03-04-05-06,-128,-1,0,1,2,3,4,127\n");
+    assert_eq!(String::from_utf8(imu.unwrap()).unwrap(),
+        "timestamp,mac,qw,qx,qy,qz,ax,ay,az,gx,gy,gz\n3000,01-02-03-04-05-06,0.75,0.5,-0.5,0.25,0,1,-1,10,20,30\n");
+
+    let data = recorder.finish().unwrap();
+    let config = CsvConfig { delimiter: '\t', header: false, timestamp_unit: TimestampUnit::Milliseconds };
+    let mut csv = CsvWriter::new(config).unwrap().emg(Vec::new());
+    assert_eq!(csv.write_events(RecordingReader::new(&data[..]).unwrap()).unwrap(), 14);
+    let (emg, imu) = csv.finish().unwrap();
+    assert_eq!(String::from_utf8(emg.unwrap()).unwrap(), "7\t01-02-03-04-05-06\t-128\t-1\t0\t1\t2\t3\t4\t127\n");
+    assert!(imu.is_none());
+
+    // fields containing the delimiter are quoted
+    let config = CsvConfig { delimiter: '-', header: true, timestamp_unit: TimestampUnit::Microseconds };
+    let mut csv = CsvWriter::new(config).unwrap().emg(Vec::new());
+    csv.write_events(RecordingReader::new(&data[..]).unwrap()).unwrap();
+    let (emg, _) = csv.finish().unwrap();
+    assert_eq!(String::from_utf8(emg.unwrap()).unwrap(),
+        "timestamp-mac-emg0-emg1-emg2-emg3-emg4-emg5-emg6-emg7\n7000-\"01-02-03-04-05-06\"-\"-128\"-\"-1\"-0-1-2-3-4-127\n");
+
+    // delimiters that cannot be quoted are rejected, before creating any file
+    for &delimiter in &['"', '\r', '\n']
+    {
+        let config = CsvConfig { delimiter, ..CsvConfig::default() };
+        let e = CsvWriter::<Vec<u8>>::new(config).err().unwrap();
+        assert_eq!(e, myo::Error::InvalidArgument(format!("CSV delimiter {:?} cannot be quoted", delimiter)));
+    }
+    let dir = std::env::temp_dir();
+    let (emg_path, imu_path) = (dir.join("myo-rejected-delimiter-emg.csv"), dir.join("myo-rejected-delimiter-imu.csv"));
+    let config = CsvConfig { delimiter: '\n', ..CsvConfig::default() };
+    assert_eq!(CsvWriter::create(&emg_path, &imu_path, config).err().unwrap().kind(), std::io::ErrorKind::InvalidInput);
+    assert!(!emg_path.exists() && !imu_path.exists());
+}
+
+#[test]
+fn jsonl_log_round_trips_every_event_type()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    push_all_types(&fake);
+    let events = hub.poll(10).unwrap();
+
+    let mut log = JsonlWriter::new(Vec::new());
+    push_all_types(&fake);
+    hub.run(10, &mut log).unwrap();
+    let data = log.finish().unwrap();
+    let text = String::from_utf8(data.clone()).unwrap();
+    assert_eq!(text.lines().count(), 14);
+    assert!(text.lines().next().unwrap().contains(r#""type":"paired""#));
+    assert!(text.contains(r#""x_direction":"toward_wrist""#));
+    assert!(text.contains(r#""pose":"wave_in""#));
+
+    let read = JsonlReader::new(&data[..]).collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(read, events);
+
+    // blank lines are skipped, malformed ones are reported
+    let mut reader = JsonlReader::new(&b"\n{\"type\":\"locked\",\"timestamp\":5,\"mac\":\"01-02-03-04-05-06\",\"name\":\"\"}\n{\"type\":\"jump\"}\n"[..]);
+    assert_eq!(reader.next().unwrap().unwrap().timestamp(), 5);
+    assert_eq!(reader.next().unwrap().unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn serde_uses_names_for_enums_and_mac_addresses()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    push_all_types(&fake);
+    let events = hub.poll(10).unwrap();
+
+    assert_eq!(serde_json::to_string(&Pose::double_tap).unwrap(), r#""double_tap""#);
+    assert_eq!(serde_json::to_string(&EventType::BatteryLevel).unwrap(), r#""battery_level""#);
+    assert_eq!(serde_json::to_string(&myo::LockingPolicy::Standard).unwrap(), r#""standard""#);
+    assert_eq!(serde_json::from_str::<VibrationType>(r#""medium""#).unwrap(), VibrationType::Medium);
+    assert_eq!(serde_json::from_str::<myo::UnlockType>(r#""hold""#).unwrap(), myo::UnlockType::Hold);
+    assert!(serde_json::from_str::<XDirection>(r#""TowardWrist""#).unwrap_err().to_string().contains("toward_wrist"));
+
+    let mac_address = events[0].mac_address();
+    assert_eq!(serde_json::to_string(&mac_address).unwrap(), r#""01-02-03-04-05-06""#);
+    assert_eq!(serde_json::from_str::<myo::MACAddress>(r#""01-02-03-04-05-06""#).unwrap(), mac_address);
+    assert!(serde_json::from_str::<myo::MACAddress>(r#""01-02-03-04-05""#).is_err());
+
+    let json = serde_json::to_string(&events).unwrap();
+    assert!(json.contains(r#"{"type":"arm_synced","timestamp":2000,"mac":"01-02-03-04-05-06","name":"my myo","arm":"left","#));
+    assert_eq!(serde_json::from_str::<Vec<MyoEvent>>(&json).unwrap(), events);
+    assert!(serde_json::from_str::<MyoEvent>(r#"{"type":"pose","timestamp":1,"mac":"01-02-03-04-05-06","name":""}"#).unwrap_err().to_string()
+        .contains("missing field `pose`"));
+}
+
+#[test]
+fn jsonl_lines_and_serde_share_one_format()
+{
+    let (_g, fake) = setup();
+    let hub = myo::Hub::init("rs.cterm2.myo.test").unwrap();
+    push_all_types(&fake);
+    let events = hub.poll(10).unwrap();
+
+    // JsonlWriter -> serde_json
+    let mut log = JsonlWriter::new(Vec::new());
+    for e in &events { log.write_event(e).unwrap(); }
+    let text = String::from_utf8(log.finish().unwrap()).unwrap();
+    let read = text.lines().map(serde_json::from_str::<MyoEvent>).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(read, events);
+
+    // serde_json -> JsonlReader
+    let lines = events.iter().map(|e| serde_json::to_string(e).unwrap() + "\n").collect::<String>();
+    assert_eq!(lines, text);
+    let read = JsonlReader::new(lines.as_bytes()).collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(read, events);
+    assert_eq!(events.iter().map(|e| MyoEvent::from_json(&e.to_json()).unwrap()).collect::<Vec<_>>(), events);
+}
@@ -0,0 +1,80 @@
+//! Asynchronous event stream against the fake libmyo.
+
+extern crate myo;
+extern crate fake_libmyo;
+extern crate futures_executor;
+
+mod common;
+use common::{setup, event};
+
+use myo::{EventType, StreamConfig, DropPolicy, VibrationType};
+use fake_libmyo::{FakeCall, CallKind, ResultCode};
+use futures_executor::{block_on, block_on_stream};
+use std::time::{Duration, Instant};
+
+fn wait_until<F: FnMut() -> bool>(mut f: F)
+{
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !f() { assert!(Instant::now() < deadline, "timed out"); std::thread::sleep(Duration::from_millis(1)); }
+}
+
+#[test]
+fn stream_yields_events_then_error()
+{
+    let (_g, fake) = setup();
+    for t in 0 .. 3 { fake.push(event(EventType::Pose, t), ""); }
+    let stream = myo::Hub::init("rs.cterm2.myo.test").unwrap().stream(StreamConfig { slice_ms: 5, .. StreamConfig::default() });
+    let mut events = block_on_stream(stream);
+    for t in 0 .. 3 { assert_eq!(events.next().unwrap().unwrap().timestamp(), t); }
+    // the hub thread shuts the hub down as soon as it stops
+    fake.fail_next("libmyo_shutdown_hub", ResultCode::Error, "hub is busy");
+    fake.fail_next("libmyo_run", ResultCode::Runtime, "connection lost");
+    assert_eq!(events.next().unwrap().err().unwrap().kind(), myo::ResultCode::Runtime);
+    assert!(events.next().is_none());
+    assert_eq!(events.into_inner().shutdown(), Err(myo::Error::Generic("hub is busy".to_owned())));
+}
+
+#[test]
+fn high_rate_events_follow_drop_policy()
+{
+    let (_g, fake) = setup();
+    for &(policy, kept) in &[(DropPolicy::DropNewest, [0, 1]), (DropPolicy::DropOldest, [3, 4])]
+    {
+        fake.reset();
+        for t in 0 .. 5 { fake.push(event(EventType::EMG, t), ""); }
+        let config = StreamConfig { capacity: 2, emg: policy, slice_ms: 5, .. StreamConfig::default() };
+        let stream = myo::Hub::init("rs.cterm2.myo.test").unwrap().stream(config);
+        wait_until(|| stream.dropped() == 3);
+        let mut events = block_on_stream(stream);
+        for &t in &kept { assert_eq!(events.next().unwrap().unwrap().timestamp(), t); }
+    }
+
+    // other events wait for room instead of being dropped
+    fake.reset();
+    for t in 0 .. 5 { fake.push(event(EventType::Pose, t), ""); }
+    let stream = myo::Hub::init("rs.cterm2.myo.test").unwrap().stream(StreamConfig { capacity: 2, slice_ms: 5, .. StreamConfig::default() });
+    let mut events = block_on_stream(stream);
+    for t in 0 .. 5 { assert_eq!(events.next().unwrap().unwrap().timestamp(), t); }
+}
+
+#[test]
+fn commands_are_marshalled_onto_hub_thread()
+{
+    let (_g, fake) = setup();
+    let stream = myo::Hub::init("rs.cterm2.myo.test").unwrap().stream(StreamConfig { slice_ms: 5, .. StreamConfig::default() });
+    let commander = stream.commander();
+    fake.push(event(EventType::Connected, 1), "");
+    let mut events = block_on_stream(stream);
+    let mac = events.next().unwrap().unwrap().mac_address();
+
+    block_on(commander.vibrate(mac, VibrationType::Long)).unwrap();
+    assert_eq!(fake.calls(), vec![FakeCall { kind: CallKind::Vibrate, myo: 1, argument: VibrationType::Long as _ }]);
+
+    fake.push(event(EventType::Disconnected, 2), "");
+    assert_eq!(events.next().unwrap().unwrap().event_type(), EventType::Disconnected);
+    assert_eq!(block_on(commander.set_stream_emg(mac, true)).err().unwrap().kind(), myo::ResultCode::Error);
+
+    let reply = commander.vibrate(mac, VibrationType::Short);
+    drop(events);
+    assert!(block_on(reply).is_err());
+}
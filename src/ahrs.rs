@@ -0,0 +1,212 @@
+//! Sensor Fusion(AHRS) from Raw Accelerometer and Gyroscope Data
+
+use {EventListener, Event, HandlerResult, MACAddress, MyoEvent, Quaternion, Vec3, IntoHandlerResult};
+use {OrientationEvent, DisconnectedEvent, UnpairedEvent};
+use per_device::PerDevice;
+
+/// Attitude filter integrating gyroscope readings, corrected toward the gravity measured by the accelerometer.
+/// Without a magnetometer the yaw is integrated only and drifts like the one of the armband.
+pub trait AhrsFilter
+{
+    /// Integrate a gyroscope reading(rad/s) over `dt` seconds with an accelerometer reading(any unit),
+    /// and return the new orientation. A zero accelerometer reading only integrates the gyroscope.
+    fn update(&mut self, gyroscope: Vec3, accelerometer: Vec3, dt: f32) -> Quaternion;
+    /// Current Orientation
+    fn orientation(&self) -> Quaternion;
+    /// Restart from `orientation`
+    fn set_orientation(&mut self, orientation: Quaternion);
+}
+
+/// Gravity in the sensor frame for an orientation
+fn gravity(q: Quaternion) -> Vec3
+{
+    Vec3::new(2.0 * (q.x * q.z - q.w * q.y), 2.0 * (q.w * q.x + q.y * q.z), 1.0 - 2.0 * (q.x * q.x + q.y * q.y))
+}
+/// Rate of change of `q` rotating at `rate`(rad/s, sensor frame)
+fn derivative(q: Quaternion, rate: Vec3) -> Quaternion { (q * Quaternion::new(rate.x, rate.y, rate.z, 0.0)).scale(0.5) }
+
+/// Madgwick's gradient descent filter.
+/// `beta`(rad/s) weights the accelerometer: larger values correct the tilt faster and pass more of the linear acceleration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Madgwick { pub beta: f32, orientation: Quaternion }
+impl Madgwick
+{
+    pub fn new(beta: f32) -> Self { Madgwick { beta, orientation: Quaternion::identity() } }
+}
+impl Default for Madgwick
+{
+    fn default() -> Self { Madgwick::new(0.1) }
+}
+impl AhrsFilter for Madgwick
+{
+    fn update(&mut self, gyroscope: Vec3, accelerometer: Vec3, dt: f32) -> Quaternion
+    {
+        let q = self.orientation;
+        let mut rate = derivative(q, gyroscope);
+        if accelerometer.length() > 0.0
+        {
+            // gradient of |gravity(q) - a|^2 / 2
+            let f = gravity(q) - accelerometer.normalize();
+            let step = Quaternion::new(
+                2.0 * q.z * f.x + 2.0 * q.w * f.y - 4.0 * q.x * f.z,
+                -2.0 * q.w * f.x + 2.0 * q.z * f.y - 4.0 * q.y * f.z,
+                2.0 * q.x * f.x + 2.0 * q.y * f.y,
+                -2.0 * q.y * f.x + 2.0 * q.x * f.y);
+            let n = step.norm();
+            if n > 0.0 { rate = rate + step.scale(-self.beta / n); }
+        }
+        self.orientation = (q + rate.scale(dt)).normalize();
+        self.orientation
+    }
+    fn orientation(&self) -> Quaternion { self.orientation }
+    fn set_orientation(&mut self, orientation: Quaternion) { self.orientation = orientation.normalize(); }
+}
+
+/// Mahony's complementary filter with a proportional(`kp`) and an integral(`ki`) gain.
+/// The integral term learns a constant gyroscope bias, except the one around the vertical axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mahony { pub kp: f32, pub ki: f32, orientation: Quaternion, integral: Vec3 }
+impl Mahony
+{
+    pub fn new(kp: f32, ki: f32) -> Self { Mahony { kp, ki, orientation: Quaternion::identity(), integral: Vec3::new(0.0, 0.0, 0.0) } }
+    /// Correction accumulated by the integral term(rad/s)
+    pub fn integral(&self) -> Vec3 { self.integral }
+}
+impl Default for Mahony
+{
+    fn default() -> Self { Mahony::new(1.0, 0.0) }
+}
+impl AhrsFilter for Mahony
+{
+    fn update(&mut self, gyroscope: Vec3, accelerometer: Vec3, dt: f32) -> Quaternion
+    {
+        let q = self.orientation;
+        let mut rate = gyroscope;
+        if accelerometer.length() > 0.0
+        {
+            let error = accelerometer.normalize().cross(gravity(q));
+            if self.ki > 0.0 { self.integral = self.integral + error * (self.ki * dt); }
+            rate = rate + error * self.kp + self.integral;
+        }
+        self.orientation = (q + derivative(q, rate).scale(dt)).normalize();
+        self.orientation
+    }
+    fn orientation(&self) -> Quaternion { self.orientation }
+    fn set_orientation(&mut self, orientation: Quaternion) { self.orientation = orientation.normalize(); self.integral = Vec3::new(0.0, 0.0, 0.0); }
+}
+
+/// Orientation of an armband from a `SensorFusion`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FusedOrientation
+{
+    /// Timestamp of the Orientation event
+    pub timestamp: u64,
+    /// Orientation from the filter
+    pub orientation: Quaternion,
+    /// Orientation from the armband, for comparison
+    pub device: Quaternion
+}
+
+/// Filter of an armband and the timestamp it has been integrated to
+struct FusedDevice<F> { filter: F, timestamp: u64 }
+
+/// Event Listener running an `AhrsFilter` on the accelerometer and gyroscope(deg/s) readings of every armband,
+/// integrating over the time between Orientation events.
+///
+/// Each armband gets a copy of the filter given to `new`, which starts from the orientation of the armband
+/// at its first Orientation event so that both can be compared. Disconnected and Unpaired events drop the filter of the armband.
+pub struct SensorFusion<'s, F: AhrsFilter + Clone>
+{
+    filter: F,
+    devices: PerDevice<'s, FusedDevice<F>, FusedOrientation>
+}
+impl<'s, F: AhrsFilter + Clone> SensorFusion<'s, F>
+{
+    pub fn new(filter: F) -> Self { SensorFusion { filter, devices: PerDevice::new() } }
+    /// Call `handler` with each fused orientation
+    pub fn on_update<H, R>(mut self, handler: H) -> Self where H: FnMut(MACAddress, &FusedOrientation) -> R + 's, R: IntoHandlerResult
+    {
+        self.devices.on_output(handler);
+        self
+    }
+    /// Filter of an armband
+    pub fn filter(&self, mac_address: MACAddress) -> Option<&F> { self.devices.get(mac_address).map(|d| &d.filter) }
+    /// Fused orientation of an armband
+    pub fn orientation(&self, mac_address: MACAddress) -> Option<Quaternion> { self.filter(mac_address).map(F::orientation) }
+
+    /// Apply an owned Orientation, Disconnected or Unpaired event; other events are ignored
+    pub fn update(&mut self, event: &MyoEvent) -> HandlerResult
+    {
+        match *event
+        {
+            MyoEvent::Orientation(ref i, ref d) => self.update_orientation(i.mac_address, i.timestamp, d.orientation, d.accelerometer, d.gyroscope),
+            MyoEvent::Disconnected(ref i) | MyoEvent::Unpaired(ref i) => { self.devices.remove(i.mac_address); HandlerResult::Continue },
+            _ => HandlerResult::Continue
+        }
+    }
+
+    fn update_orientation(&mut self, mac_address: MACAddress, timestamp: u64, device: Quaternion, accelerometer: Vec3, gyroscope: Vec3) -> HandlerResult
+    {
+        let template = &self.filter;
+        let d = self.devices.entry(mac_address, ||
+        {
+            let mut filter = template.clone();
+            filter.set_orientation(device);
+            FusedDevice { filter, timestamp }
+        });
+        if timestamp > d.timestamp
+        {
+            let dt = (timestamp - d.timestamp) as f32 / 1_000_000.0;
+            d.filter.update(gyroscope * 1.0f32.to_radians(), accelerometer, dt);
+        }
+        // timestamps going back(e.g. a looping replay) restart the integration
+        else if timestamp < d.timestamp { d.filter.set_orientation(device); }
+        d.timestamp = timestamp;
+        let o = FusedOrientation { timestamp, orientation: d.filter.orientation(), device };
+        self.devices.notify(mac_address, &o)
+    }
+}
+impl<'s, F: AhrsFilter + Clone> EventListener for SensorFusion<'s, F>
+{
+    fn on_disconnected(&mut self, event: DisconnectedEvent) -> HandlerResult { self.devices.remove(event.mac_address()); HandlerResult::Continue }
+    fn on_unpaired(&mut self, event: UnpairedEvent) -> HandlerResult { self.devices.remove(event.mac_address()); HandlerResult::Continue }
+    fn on_orientation_data(&mut self, event: OrientationEvent) -> HandlerResult
+    {
+        self.update_orientation(event.mac_address(), event.timestamp(), event.q_orientation(), event.v_accelerometer(), event.v_gyroscope())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::{AhrsFilter, Madgwick, Mahony};
+    use {Quaternion, Vec3};
+
+    #[test]
+    fn gains_control_the_tilt_correction()
+    {
+        let level = Vec3::new(0.0, 0.0, 1.0);
+        let tilted = Quaternion::from_euler_angles(0.5, 0.0, 0.0);
+        let settle = |f: &mut dyn AhrsFilter, gyroscope: Vec3, steps: usize|
+        {
+            f.set_orientation(tilted);
+            for _ in 0 .. steps { f.update(gyroscope, level, 0.02); }
+            f.orientation().roll().abs()
+        };
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        let (slow, fast) = (settle(&mut Madgwick::new(0.1), zero, 50), settle(&mut Madgwick::new(1.0), zero, 50));
+        assert!(fast < slow && slow < 0.5, "{} {}", slow, fast);
+        let (slow, fast) = (settle(&mut Mahony::new(0.5, 0.0), zero, 50), settle(&mut Mahony::new(5.0, 0.0), zero, 50));
+        assert!(fast < slow && slow < 0.5, "{} {}", slow, fast);
+        // without an accelerometer reading only the gyroscope is integrated
+        let mut f = Madgwick::new(1.0);
+        for _ in 0 .. 50 { f.update(Vec3::new(1.0, 0.0, 0.0), zero, 0.01); }
+        assert!((f.orientation().roll() - 0.5).abs() < 1e-3);
+
+        // the integral term learns a gyroscope bias
+        let bias = Vec3::new(0.05, -0.02, 0.0);
+        let mut f = Mahony::new(1.0, 0.1);
+        assert!(settle(&mut f, bias, 5000) < 1e-3);
+        assert!((f.integral() + bias).length() < 1e-3, "{:?}", f.integral());
+    }
+}
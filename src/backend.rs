@@ -0,0 +1,92 @@
+//! Hub Backend Abstraction
+
+use {Result, MyoString, HandlerResult, LockingPolicy, VibrationType, UnlockType, VersionComponent, EventType,
+    Arm, XDirection, WarmupState, WarmupResult, Pose, OrientationIndex};
+
+/// Event Handler passed to `Backend::run`
+pub type BackendHandler<'a> = dyn FnMut(&dyn RawEvent) -> HandlerResult + 'a;
+
+/// Hub Backend: source of events and sink of armband commands
+pub trait Backend
+{
+    /// Set the locking policy for Myos connected to the hub.
+    fn set_locking_policy(&self, locking_policy: LockingPolicy) -> Result<()>;
+    /// Process events for up to approximately `duration_ms` milliseconds, passing each to `handler`.
+    /// Returns early when `handler` returns `HandlerResult::Stop`.
+    fn run(&self, duration_ms: u32, handler: &mut BackendHandler) -> Result<()>;
+    /// Release the resources of the backend(`Hub::shutdown`). Called at most once, and nothing else is called afterwards.
+    fn shutdown(&mut self) -> Result<()> { Ok(()) }
+}
+
+/// Raw Event Data provided by a backend.
+/// Accessors for fields that do not belong to the event's type may return any value.
+pub trait RawEvent
+{
+    /// Event Type
+    fn event_type(&self) -> EventType;
+    /// Timestamp
+    fn timestamp(&self) -> u64;
+    /// Myo Device
+    fn device(&self) -> &dyn Device;
+    /// MAC Address(48-bit)
+    fn mac_address(&self) -> u64;
+    /// Myo Name
+    fn device_name(&self) -> MyoString;
+    /// Firmware Version(Paired/Connected)
+    fn firmware_version(&self, component: VersionComponent) -> u32;
+    /// Arm Side(ArmSynced)
+    fn arm(&self) -> Arm;
+    /// +x Direction(ArmSynced)
+    fn x_direction(&self) -> XDirection;
+    /// Warming up state(ArmSynced)
+    fn warmup_state(&self) -> WarmupState;
+    /// Warmup Result(WarmupCompleted)
+    fn warmup_result(&self) -> WarmupResult;
+    /// Estimated Rotation of Myo on the user's arm(ArmSynced)
+    fn rotation_on_arm(&self) -> f32;
+    /// Orientation data(Orientation)
+    fn orientation(&self, index: OrientationIndex) -> f32;
+    /// Accelerometer data in units of g(Orientation)
+    fn accelerometer(&self, index: u32) -> f32;
+    /// Gyroscope data in units of deg/s(Orientation)
+    fn gyroscope(&self, index: u32) -> f32;
+    /// Pose(Pose)
+    fn pose(&self) -> Pose;
+    /// RSSI(RSSI)
+    fn rssi(&self) -> i8;
+    /// Battery Level(BatteryLevel)
+    fn battery_level(&self) -> u8;
+    /// EMG data point(EMG), `sensor` must be smaller than 8
+    fn emg(&self, sensor: u32) -> i8;
+}
+
+/// Armband Control Command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command
+{
+    /// Vibrate
+    Vibrate(VibrationType),
+    /// Asynchronous Request: RSSI
+    RequestRSSI,
+    /// Asynchronous Request: Battery Level
+    RequestBatteryLevel,
+    /// Stream EMG Data
+    SetStreamEMG(bool),
+    /// Lock Armband
+    Lock,
+    /// Unlock Armband
+    Unlock(UnlockType),
+    /// Notify Myo device that a user action was recognized.
+    NotifyUserAction
+}
+
+/// Myo Device provided by a backend
+pub trait Device
+{
+    /// Backend-specific identifier
+    fn raw_id(&self) -> usize;
+    /// Execute a command on the device
+    fn execute(&self, command: Command) -> Result<()>;
+    /// Copy of the device that outlives the event, usable until the device is unpaired
+    fn retain(&self) -> Box<dyn Device + Send>;
+}
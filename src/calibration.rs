@@ -0,0 +1,125 @@
+//! Reference-frame Calibration of Orientations
+
+use {EventListener, Event, HandlerResult, MACAddress, MyoEvent, Pose, Quaternion, Vec3, IntoHandlerResult};
+use {OrientationEvent, PoseEvent};
+use per_device::PerDevice;
+
+/// Orientation of an armband relative to its reference(`Calibrator`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RelativeOrientation
+{
+    /// Timestamp of the Orientation event
+    pub timestamp: u64,
+    /// Orientation in the frame of the reference, or the raw orientation without a reference
+    pub orientation: Quaternion,
+    /// Whether a reference has been captured
+    pub calibrated: bool
+}
+
+/// Reference and last orientation of an armband
+#[derive(Default)]
+struct Reference { reference: Option<Quaternion>, last: Option<Quaternion> }
+
+/// Event Listener reporting orientations relative to a captured reference orientation("neutral pose") of each armband.
+/// The relative orientation is `reference.conjugate() * orientation`: the reference reads as the identity.
+///
+/// References are captured from the last Orientation event with `capture`, or on a chosen pose with `capture_on`.
+/// `recenter_yaw` turns the reference around the vertical axis only, to cancel the yaw drift without touching the tilt.
+pub struct Calibrator<'c>
+{
+    devices: PerDevice<'c, Reference, RelativeOrientation>,
+    capture_pose: Option<Pose>, recenter_pose: Option<Pose>
+}
+impl<'c> Calibrator<'c>
+{
+    pub fn new() -> Self { Calibrator { devices: PerDevice::new(), capture_pose: None, recenter_pose: None } }
+    /// Capture the reference when the armband recognizes `pose`, e.g. `Pose::double_tap`
+    pub fn capture_on(mut self, pose: Pose) -> Self { self.capture_pose = Some(pose); self }
+    /// Re-center the yaw of the reference when the armband recognizes `pose`
+    pub fn recenter_yaw_on(mut self, pose: Pose) -> Self { self.recenter_pose = Some(pose); self }
+    /// Call `handler` with each relative orientation
+    pub fn on_update<F, R>(mut self, handler: F) -> Self where F: FnMut(MACAddress, &RelativeOrientation) -> R + 'c, R: IntoHandlerResult
+    {
+        self.devices.on_output(handler);
+        self
+    }
+
+    /// Use the last orientation of an armband as its reference.
+    /// False, keeping the current reference, if no orientation has been received.
+    pub fn capture(&mut self, mac_address: MACAddress) -> bool
+    {
+        let d = self.devices.entry(mac_address, Reference::default);
+        if let Some(q) = d.last { d.reference = Some(q.normalize()); }
+        d.last.is_some()
+    }
+    /// Use `reference` as the reference of an armband
+    pub fn set_reference(&mut self, mac_address: MACAddress, reference: Quaternion)
+    {
+        self.devices.entry(mac_address, Reference::default).reference = Some(reference.normalize());
+    }
+    /// Drop the reference of an armband
+    pub fn clear(&mut self, mac_address: MACAddress) { self.devices.entry(mac_address, Reference::default).reference = None; }
+    /// Turn the reference around the vertical axis to the yaw of the last orientation of an armband,
+    /// keeping its roll and pitch. Without a reference the yaw alone becomes the reference.
+    /// False if no orientation has been received.
+    pub fn recenter_yaw(&mut self, mac_address: MACAddress) -> bool
+    {
+        let d = self.devices.entry(mac_address, Reference::default);
+        let last = match d.last { Some(q) => q, None => return false };
+        let reference = d.reference.unwrap_or_else(Quaternion::identity);
+        let turn = Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), last.yaw() - reference.yaw());
+        d.reference = Some((turn * reference).normalize());
+        true
+    }
+    /// Reference of an armband
+    pub fn reference(&self, mac_address: MACAddress) -> Option<Quaternion> { self.devices.get(mac_address).and_then(|d| d.reference) }
+    /// Last orientation of an armband relative to its reference
+    pub fn relative(&self, mac_address: MACAddress) -> Option<Quaternion>
+    {
+        self.devices.get(mac_address).and_then(|d| d.last.map(|q| Self::apply(d.reference, q)))
+    }
+
+    /// Apply an owned Orientation or Pose event; other events are ignored
+    pub fn update(&mut self, event: &MyoEvent) -> HandlerResult
+    {
+        match *event
+        {
+            MyoEvent::Orientation(ref i, ref d) => self.update_orientation(i.mac_address, i.timestamp, d.orientation),
+            MyoEvent::Pose(ref i, p) => { self.update_pose(i.mac_address, p); HandlerResult::Continue },
+            _ => HandlerResult::Continue
+        }
+    }
+
+    fn apply(reference: Option<Quaternion>, orientation: Quaternion) -> Quaternion
+    {
+        match reference
+        {
+            Some(r) => (r.conjugate() * orientation).normalize(),
+            None => orientation
+        }
+    }
+    fn update_pose(&mut self, mac_address: MACAddress, pose: Pose)
+    {
+        if self.capture_pose == Some(pose) { self.capture(mac_address); }
+        else if self.recenter_pose == Some(pose) { self.recenter_yaw(mac_address); }
+    }
+    fn update_orientation(&mut self, mac_address: MACAddress, timestamp: u64, orientation: Quaternion) -> HandlerResult
+    {
+        let d = self.devices.entry(mac_address, Reference::default);
+        d.last = Some(orientation);
+        let o = RelativeOrientation { timestamp, orientation: Self::apply(d.reference, orientation), calibrated: d.reference.is_some() };
+        self.devices.notify(mac_address, &o)
+    }
+}
+impl<'c> Default for Calibrator<'c>
+{
+    fn default() -> Self { Self::new() }
+}
+impl<'c> EventListener for Calibrator<'c>
+{
+    fn on_pose(&mut self, event: PoseEvent) -> HandlerResult { self.update_pose(event.mac_address(), event.pose()); HandlerResult::Continue }
+    fn on_orientation_data(&mut self, event: OrientationEvent) -> HandlerResult
+    {
+        self.update_orientation(event.mac_address(), event.timestamp(), event.q_orientation())
+    }
+}
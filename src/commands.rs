@@ -0,0 +1,105 @@
+//! Cross-thread Command Queue
+
+use {Hub, Backend, Result, Error, MACAddress, Command, VibrationType, UnlockType};
+use std::sync::mpsc::{channel, Sender, Receiver, TryRecvError, RecvTimeoutError};
+use std::time::Duration;
+
+/// Called with the result of a queued command
+pub(crate) type Completion = Box<dyn FnOnce(Result<()>) + Send>;
+pub(crate) struct QueuedCommand { mac_address: MACAddress, command: Command, completion: Completion }
+
+/// Both ends of the command queue of a hub
+pub(crate) struct CommandChannel { sender: Sender<QueuedCommand>, receiver: Receiver<QueuedCommand> }
+impl Default for CommandChannel
+{
+    fn default() -> Self { let (sender, receiver) = channel(); CommandChannel { sender, receiver } }
+}
+
+fn stopped() -> Error { Error::Generic("the hub stopped before executing the command".to_owned()) }
+
+impl<B: Backend> Hub<B>
+{
+    /// Cloneable handle to queue armband commands from any thread
+    pub fn command_queue(&self) -> CommandQueue { CommandQueue(self.commands.sender.clone()) }
+    /// Execute the queued commands on this thread and return their number.
+    /// `Hub::run` calls this before processing events, so that loops calling it in slices apply commands between the slices.
+    pub fn apply_commands(&self) -> usize
+    {
+        let mut count = 0;
+        for c in self.commands.receiver.try_iter()
+        {
+            (c.completion)(self.armband(c.mac_address).execute(c.command));
+            count += 1;
+        }
+        count
+    }
+}
+
+/// Cloneable, `Send` handle to queue commands for the armbands of a hub(`Hub::command_queue`).
+/// Commands are executed in order by the thread running the hub, the next time it calls `Hub::run`.
+#[derive(Clone)]
+pub struct CommandQueue(Sender<QueuedCommand>);
+impl CommandQueue
+{
+    /// Queue `command` for the armband with `mac_address`
+    pub fn send(&self, mac_address: MACAddress, command: Command) -> CommandResult
+    {
+        let (sender, receiver) = channel();
+        self.send_with(mac_address, command, Box::new(move |r| { let _ = sender.send(r); }));
+        CommandResult(receiver)
+    }
+    /// Queue `command`, calling `completion` with its result on the hub thread.
+    /// A completion that is never called(the hub was dropped) is dropped instead.
+    pub(crate) fn send_with(&self, mac_address: MACAddress, command: Command, completion: Completion)
+    {
+        if let Err(e) = self.0.send(QueuedCommand { mac_address, command, completion })
+        {
+            (e.0.completion)(Err(stopped()));
+        }
+    }
+    /// Queue commands for the armband with `mac_address`
+    pub fn armband(&self, mac_address: MACAddress) -> QueuedArmband
+    {
+        QueuedArmband { queue: self.clone(), mac_address }
+    }
+}
+
+/// Armband whose commands go through a `CommandQueue`
+#[derive(Clone)]
+pub struct QueuedArmband { queue: CommandQueue, mac_address: MACAddress }
+impl QueuedArmband
+{
+    /// MAC Address
+    pub fn mac_address(&self) -> MACAddress { self.mac_address }
+    /// Queue a command
+    pub fn execute(&self, command: Command) -> CommandResult { self.queue.send(self.mac_address, command) }
+    DefArmbandCommands!(CommandResult);
+}
+
+/// Completion result of a queued command
+pub struct CommandResult(Receiver<Result<()>>);
+impl CommandResult
+{
+    /// Block until the command has been executed
+    pub fn wait(self) -> Result<()> { self.0.recv().unwrap_or_else(|_| Err(stopped())) }
+    /// Block until the command has been executed, or fail with `Error::Timeout` after `timeout`
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<()>
+    {
+        match self.0.recv_timeout(timeout)
+        {
+            Ok(r) => r,
+            Err(RecvTimeoutError::Timeout) => Err(Error::Timeout(timeout)),
+            Err(RecvTimeoutError::Disconnected) => Err(stopped())
+        }
+    }
+    /// Take the result of the command if it has been executed
+    pub fn try_result(&self) -> Option<Result<()>>
+    {
+        match self.0.try_recv()
+        {
+            Ok(r) => Some(r),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(stopped()))
+        }
+    }
+}
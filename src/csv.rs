@@ -0,0 +1,171 @@
+//! CSV Export of EMG and IMU Data
+
+use {Result, Error, EventListener, EventSink, Event, HandlerResult, MyoEvent, OrientationEvent, EMGEvent};
+use std::io::{self, Write, BufWriter};
+use std::fs::File;
+use std::path::Path;
+
+/// Unit of the timestamp column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampUnit
+{
+    /// Microseconds as an integer(the unit of event timestamps)
+    Microseconds,
+    /// Milliseconds with a fraction
+    Milliseconds,
+    /// Seconds with a fraction
+    Seconds
+}
+
+/// Format of a `CsvWriter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvConfig
+{
+    /// Field Delimiter: fields containing it are quoted.
+    /// `CsvWriter::new` rejects a quote or a line break.
+    pub delimiter: char,
+    /// Write a header row first
+    pub header: bool,
+    /// Unit of the timestamp column
+    pub timestamp_unit: TimestampUnit
+}
+impl Default for CsvConfig
+{
+    fn default() -> Self { CsvConfig { delimiter: ',', header: true, timestamp_unit: TimestampUnit::Microseconds } }
+}
+
+const EMG_COLUMNS: [&str; 10] = ["timestamp", "mac", "emg0", "emg1", "emg2", "emg3", "emg4", "emg5", "emg6", "emg7"];
+const IMU_COLUMNS: [&str; 12] = ["timestamp", "mac", "qw", "qx", "qy", "qz", "ax", "ay", "az", "gx", "gy", "gz"];
+
+/// A field quoted if it contains the delimiter(e.g. a `-` delimiter and a MAC address)
+fn quote(field: &str, delimiter: char) -> String
+{
+    if field.contains(delimiter) { format!("\"{}\"", field) } else { field.to_owned() }
+}
+
+/// Output of one kind of rows
+struct Table<W: Write> { writer: W, columns: &'static [&'static str], started: bool }
+impl<W: Write> Table<W>
+{
+    fn row(&mut self, config: &CsvConfig, fields: &[String]) -> io::Result<()>
+    {
+        let d = config.delimiter.to_string();
+        if !self.started && config.header
+        {
+            writeln!(self.writer, "{}", self.columns.iter().map(|c| quote(c, config.delimiter)).collect::<Vec<_>>().join(&d))?;
+        }
+        self.started = true;
+        writeln!(self.writer, "{}", fields.iter().map(|f| quote(f, config.delimiter)).collect::<Vec<_>>().join(&d))
+    }
+}
+
+/// Event Listener writing EMG rows(`timestamp, mac, emg0..emg7`) and IMU rows
+/// (`timestamp, mac, qw, qx, qy, qz, ax, ay, az, gx, gy, gz`) to separate outputs.
+/// Other events are ignored. Write errors stop the run and are kept in `error`.
+pub struct CsvWriter<W: Write = BufWriter<File>>
+{
+    config: CsvConfig, emg: Option<Table<W>>, imu: Option<Table<W>>, error: Option<io::Error>
+}
+impl CsvWriter
+{
+    /// Create EMG and IMU files, replacing existing ones
+    pub fn create<P: AsRef<Path>, Q: AsRef<Path>>(emg_path: P, imu_path: Q, config: CsvConfig) -> io::Result<Self>
+    {
+        let writer = CsvWriter::new(config).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+        Ok(writer.emg(BufWriter::new(File::create(emg_path)?)).imu(BufWriter::new(File::create(imu_path)?)))
+    }
+}
+impl<W: Write> CsvWriter<W>
+{
+    /// Writer without outputs: add them with `emg` and `imu`.
+    /// Fails on a delimiter that cannot be quoted(`"`, `\r` or `\n`).
+    pub fn new(config: CsvConfig) -> Result<Self>
+    {
+        if ['"', '\r', '\n'].contains(&config.delimiter)
+        {
+            return Err(Error::InvalidArgument(format!("CSV delimiter {:?} cannot be quoted", config.delimiter)));
+        }
+        Ok(CsvWriter { config, emg: None, imu: None, error: None })
+    }
+    /// Write EMG rows to `writer`
+    pub fn emg(mut self, writer: W) -> Self { self.emg = Some(Table { writer, columns: &EMG_COLUMNS, started: false }); self }
+    /// Write IMU rows to `writer`
+    pub fn imu(mut self, writer: W) -> Self { self.imu = Some(Table { writer, columns: &IMU_COLUMNS, started: false }); self }
+
+    fn timestamp(&self, t: u64) -> String
+    {
+        match self.config.timestamp_unit
+        {
+            TimestampUnit::Microseconds => t.to_string(),
+            TimestampUnit::Milliseconds => (t as f64 / 1_000.0).to_string(),
+            TimestampUnit::Seconds => (t as f64 / 1_000_000.0).to_string()
+        }
+    }
+    /// Write a row for an EMG or Orientation event
+    pub fn write_event(&mut self, event: &MyoEvent) -> io::Result<()>
+    {
+        let mut fields = vec![self.timestamp(event.timestamp()), event.mac_address().to_string()];
+        match *event
+        {
+            MyoEvent::EMG(_, ref v) => if let Some(ref mut t) = self.emg
+            {
+                fields.extend(v.iter().map(|x| x.to_string()));
+                t.row(&self.config, &fields)?;
+            },
+            MyoEvent::Orientation(_, ref d) => if let Some(ref mut t) = self.imu
+            {
+                let (q, a, g) = (d.orientation, d.accelerometer, d.gyroscope);
+                fields.extend([q.w, q.x, q.y, q.z, a.x, a.y, a.z, g.x, g.y, g.z].iter().map(|x| x.to_string()));
+                t.row(&self.config, &fields)?;
+            },
+            _ => ()
+        }
+        Ok(())
+    }
+    /// Write rows for a sequence of events, e.g. a `RecordingReader`, and return the number of events read
+    pub fn write_events<I: IntoIterator<Item = io::Result<MyoEvent>>>(&mut self, events: I) -> io::Result<u64>
+    {
+        let mut count = 0;
+        for e in events { self.write_event(&e?)?; count += 1; }
+        Ok(count)
+    }
+    /// Write buffered rows
+    pub fn flush(&mut self) -> io::Result<()>
+    {
+        if let Some(ref mut t) = self.emg { t.writer.flush()?; }
+        if let Some(ref mut t) = self.imu { t.writer.flush()?; }
+        Ok(())
+    }
+    /// First write error that stopped the export as a listener
+    pub fn error(&self) -> Option<&io::Error> { self.error.as_ref() }
+    /// Flush and take back the EMG and IMU writers
+    pub fn finish(mut self) -> io::Result<(Option<W>, Option<W>)>
+    {
+        if let Some(e) = self.error.take() { return Err(e); }
+        self.flush()?;
+        Ok((self.emg.map(|t| t.writer), self.imu.map(|t| t.writer)))
+    }
+
+    fn export<E: Event>(&mut self, event: &E) -> HandlerResult
+    {
+        if self.error.is_some() { return HandlerResult::Stop; }
+        match self.write_event(&event.to_owned_event())
+        {
+            Ok(()) => HandlerResult::Continue,
+            Err(e) => { self.error = Some(e); HandlerResult::Stop }
+        }
+    }
+}
+impl<W: Write> EventListener for CsvWriter<W>
+{
+    fn on_orientation_data(&mut self, event: OrientationEvent) -> HandlerResult { self.export(&event) }
+    fn on_emg_data(&mut self, event: EMGEvent) -> HandlerResult { self.export(&event) }
+}
+impl<W: Write> EventSink for CsvWriter<W>
+{
+    fn flush(&mut self) -> io::Result<()>
+    {
+        if let Some(ref e) = self.error { return Err(io::Error::new(e.kind(), e.to_string())); }
+        CsvWriter::flush(self)
+    }
+}
@@ -0,0 +1,103 @@
+//! Errors
+
+use ffi;
+use {ResultCode, MACAddress};
+use std::ffi::CStr;
+use std::fmt;
+use std::time::Duration;
+
+/// Operation Error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error
+{
+    /// An argument was rejected(`ResultCode::ErrorInvalidArgument`)
+    InvalidArgument(String),
+    /// Runtime error, e.g. lost connection to Myo Connect(`ResultCode::Runtime`)
+    Runtime(String),
+    /// Other error reported by libmyo or a backend(`ResultCode::Error`)
+    Generic(String),
+    /// libmyo could not be loaded or lacks a function
+    LibraryMissing(String),
+    /// The armband has not been seen by the hub, has been unpaired or is disconnected
+    DeviceGone { mac_address: MACAddress, reason: String },
+    /// The application identifier is not in reverse domain name format;
+    /// `source` is the error of libmyo when it rejected the identifier
+    InvalidApplicationIdentifier { identifier: String, source: Option<Box<Error>> },
+    /// An operation did not complete in time
+    Timeout(Duration)
+}
+impl Error
+{
+    /// Error of `kind` reported by a non-libmyo backend
+    pub fn new<M: Into<String>>(kind: ResultCode, message: M) -> Self
+    {
+        match kind
+        {
+            ResultCode::ErrorInvalidArgument => Error::InvalidArgument(message.into()),
+            ResultCode::Runtime => Error::Runtime(message.into()),
+            ResultCode::Success | ResultCode::Error => Error::Generic(message.into())
+        }
+    }
+    /// Copy and free libmyo error details
+    pub(crate) fn libmyo(e: ffi::libmyo_error_details_t) -> Self
+    {
+        let (kind, message) = unsafe { (ResultCode::from_raw(ffi::libmyo_error_kind(e) as _).unwrap_or(ResultCode::Error), CStr::from_ptr(ffi::libmyo_error_cstring(e)).to_string_lossy().into_owned()) };
+        unsafe { ffi::libmyo_free_error_details(e) };
+        Error::new(kind, message)
+    }
+
+    /// libmyo result code corresponding to the error
+    pub fn kind(&self) -> ResultCode
+    {
+        match *self
+        {
+            Error::InvalidArgument(_) | Error::InvalidApplicationIdentifier { .. } => ResultCode::ErrorInvalidArgument,
+            Error::Runtime(_) | Error::LibraryMissing(_) => ResultCode::Runtime,
+            Error::Generic(_) | Error::DeviceGone { .. } | Error::Timeout(_) => ResultCode::Error
+        }
+    }
+}
+impl fmt::Display for Error
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result
+    {
+        match *self
+        {
+            Error::InvalidArgument(ref m) | Error::Runtime(ref m) | Error::Generic(ref m) | Error::LibraryMissing(ref m) => fmt.write_str(m),
+            Error::DeviceGone { mac_address, ref reason } => write!(fmt, "armband {} {}", mac_address, reason),
+            Error::InvalidApplicationIdentifier { ref identifier, .. } => write!(fmt, "invalid application identifier {:?}", identifier),
+            Error::Timeout(d) => write!(fmt, "timed out after {:?}", d)
+        }
+    }
+}
+impl std::error::Error for Error
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)>
+    {
+        match *self
+        {
+            Error::InvalidApplicationIdentifier { source: Some(ref e), .. } => Some(&**e),
+            _ => None
+        }
+    }
+}
+
+/// Check the format required by libmyo: three or more segments of ASCII alphanumerics, `-` and `_` separated by `.`,
+/// where `-` and `_` are not at either end of a segment nor in the top-level domain, up to 255 characters.
+/// An empty identifier is allowed.
+pub(crate) fn validate_application_identifier(identifier: &str) -> Result<(), Error>
+{
+    if identifier.is_empty() { return Ok(()); }
+    let segments = identifier.split('.').collect::<Vec<_>>();
+    let valid_segment = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') &&
+        !s.starts_with(['-', '_']) && !s.ends_with(['-', '_']);
+    if identifier.len() <= 255 && segments.len() >= 3 && segments.iter().all(|s| valid_segment(s)) &&
+        segments[0].bytes().all(|b| b.is_ascii_alphanumeric())
+    {
+        Ok(())
+    }
+    else
+    {
+        Err(Error::InvalidApplicationIdentifier { identifier: identifier.to_owned(), source: None })
+    }
+}
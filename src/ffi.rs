@@ -143,6 +143,8 @@ pub type libmyo_event_t = *const c_void;
 /// Hardware revisions.
 #[repr(C)] #[derive(Debug, PartialEq, Eq, Clone, Copy)] pub enum HardwareRevision
 {
+    /// Unknown revision(not reported by libmyo)
+    Unknown = 0,
     /// Alpha units
     RevC = 1,
     /// Consumer units
@@ -210,17 +212,124 @@ pub type libmyo_event_t = *const c_void;
     /// Stop processing events
     Stop
 }
+/// Checked conversions of the raw integers returned by libmyo
+macro_rules! DefFromRaw
+{
+    ($($t: ident { $($v: ident),* })*) =>
+    {
+        $(
+            impl $t
+            {
+                /// Value of a raw libmyo integer, or `None` if it is not a known value
+                pub fn from_raw(value: u32) -> Option<Self>
+                {
+                    $(if value == $t::$v as u32 { return Some($t::$v); })*
+                    None
+                }
+            }
+        )*
+    }
+}
+DefFromRaw!
+{
+    ResultCode { Success, Error, ErrorInvalidArgument, Runtime }
+    EventType
+    {
+        Paired, Unpaired, Connected, Disconnected, ArmSynced, ArmUnsynced, Orientation, Pose, RSSI,
+        Unlocked, Locked, EMG, BatteryLevel, WarmupCompleted
+    }
+    HardwareRevision { Unknown, RevC, RevD }
+    Arm { Right, Left, Unknown }
+    XDirection { TowardWrist, TowardElbow, Unknown }
+    WarmupState { Unknown, Cold, Warm }
+    WarmupResult { Unknown, Success, Timeout }
+    Pose { rest, fist, wave_in, wave_out, fingers_spread, double_tap, unknown }
+}
+
 /// Callback function type to handle events as they occur from libmyo_run()
 pub type libmyo_handler_t = extern "system" fn(user_data: *mut c_void, event: libmyo_event_t) -> HandlerResult;
 
-#[cfg_attr(target_pointer_width = "32", link(name = "myo32"))]
-#[cfg_attr(target_pointer_width = "64", link(name = "myo64"))]
-extern "system"
+/// Declare libmyo functions.
+/// They are linked at build time by default, or resolved from a dynamically loaded library with the `dynamic` feature.
+macro_rules! LibmyoFunctions
+{
+    ($($(#[$m: meta])* pub fn $name: ident ( $($arg: ident: $t: ty),* ) $(-> $r: ty)?;)*) =>
+    {
+        #[cfg(not(feature = "dynamic"))]
+        #[cfg_attr(target_pointer_width = "32", link(name = "myo32"))]
+        #[cfg_attr(target_pointer_width = "64", link(name = "myo64"))]
+        extern "system"
+        {
+            $($(#[$m])* pub fn $name($($arg: $t),*) $(-> $r)?;)*
+        }
+
+        /// Resolved libmyo function table
+        #[cfg(feature = "dynamic")]
+        struct Api
+        {
+            _library: ::libloading::Library,
+            $($name: unsafe extern "system" fn($($t),*) $(-> $r)?),*
+        }
+        #[cfg(feature = "dynamic")]
+        impl Api
+        {
+            fn load(path: &::std::ffi::OsStr) -> Result<Self, String>
+            {
+                let library = unsafe { ::libloading::Library::new(path) }
+                    .map_err(|e| format!("failed to load libmyo from {:?}: {}", path, e))?;
+                $(
+                    let $name = *unsafe { library.get::<unsafe extern "system" fn($($t),*) $(-> $r)?>(concat!(stringify!($name), "\0").as_bytes()) }
+                        .map_err(|e| format!("libmyo symbol `{}` is missing: {}", stringify!($name), e))?;
+                )*
+                Ok(Api { _library: library, $($name),* })
+            }
+        }
+        $(
+            #[cfg(feature = "dynamic")]
+            $(#[$m])* pub unsafe fn $name($($arg: $t),*) $(-> $r)? { (api().$name)($($arg),*) }
+        )*
+    }
+}
+
+#[cfg(feature = "dynamic")]
+static LIBMYO: ::std::sync::OnceLock<Api> = ::std::sync::OnceLock::new();
+#[cfg(feature = "dynamic")]
+fn api() -> &'static Api { LIBMYO.get().expect("libmyo is not loaded") }
+
+/// Load libmyo from `path`. Has no effect if libmyo has already been loaded.
+#[cfg(feature = "dynamic")]
+pub fn load(path: &::std::ffi::OsStr) -> Result<(), String>
+{
+    if LIBMYO.get().is_none()
+    {
+        // a concurrent loader may win the race; either table is valid
+        let _ = LIBMYO.set(Api::load(path)?);
+    }
+    Ok(())
+}
+/// Make sure that libmyo functions are callable.
+/// Loads the library named by `MYO_LIBRARY` environment variable, or `myo64`/`myo32` from the system search path if not loaded yet.
+#[cfg(feature = "dynamic")]
+pub fn ensure_loaded() -> Result<(), String>
+{
+    if LIBMYO.get().is_some() { return Ok(()); }
+    match ::std::env::var_os("MYO_LIBRARY")
+    {
+        Some(path) => load(&path),
+        None if cfg!(target_pointer_width = "32") => load(&::libloading::library_filename("myo32")),
+        None => load(&::libloading::library_filename("myo64"))
+    }
+}
+/// Make sure that libmyo functions are callable(always linked at build time).
+#[cfg(not(feature = "dynamic"))]
+pub fn ensure_loaded() -> Result<(), String> { Ok(()) }
+
+LibmyoFunctions!
 {
     /// Return a null-terminated string with a detailed error message.
     pub fn libmyo_error_cstring(details: libmyo_error_details_t) -> *const c_char;
     /// Returns the kind of error that occured.
-    pub fn libmyo_error_kind(details: libmyo_error_details_t) -> ResultCode;
+    pub fn libmyo_error_kind(details: libmyo_error_details_t) -> c_int;
     /// Free the resources allocated by an error object.
     pub fn libmyo_free_error_details(details: libmyo_error_details_t);
 
@@ -232,9 +341,11 @@ extern "system"
     // MAC address utilities
     /// Retrieve the string representation of a MAC address in hex.
     /// Returns a string in the format of 00-00-00-00-00-00.
+    #[allow(dead_code)]
     pub fn libmyo_mac_address_to_string(addr: u64) -> libmyo_string_t;
     /// Retrieve the MAC address from a null-terminated string in the format of 00-00-00-00-00-00.
     /// Returns 0 if the string does not match the format.
+    #[allow(dead_code)]
     pub fn libmyo_string_to_mac_address(s: *const c_char) -> u64;
 
     // Hub instance
@@ -331,17 +442,17 @@ extern "system"
     pub fn libmyo_event_get_firmware_version(event: libmyo_event_t, c: VersionComponent) -> c_uint;
     /// Retrieve the arm associated with an event.
     /// Valid for libmyo_event_arm_synced events only.
-    pub fn libmyo_event_get_arm(event: libmyo_event_t) -> Arm;
+    pub fn libmyo_event_get_arm(event: libmyo_event_t) -> c_int;
     /// Retrieve the x-direction associated with an event.
     /// The x-direction specifies which way Myo's +x axis is pointing relative to the user's arm.
     /// Valid for libmyo_event_arm_synced events only.
-    pub fn libmyo_event_get_x_direction(event: libmyo_event_t) -> XDirection;
+    pub fn libmyo_event_get_x_direction(event: libmyo_event_t) -> c_int;
     /// Retrieve the warmup state of the Myo associated with an event.
     /// Valid for libmyo_event_arm_synced events only.
-    pub fn libmyo_event_get_warmup_state(event: libmyo_event_t) -> WarmupState;
+    pub fn libmyo_event_get_warmup_state(event: libmyo_event_t) -> c_int;
     /// Retrieve the warmup result of the Myo associated with an event.
     /// Valid for libmyo_event_warmup_completed events only.
-    pub fn libmyo_event_get_warmup_result(event: libmyo_event_t) -> WarmupResult;
+    pub fn libmyo_event_get_warmup_result(event: libmyo_event_t) -> c_int;
     /// Retrieve the estimated rotation of Myo on the user's arm after a sync.
     /// The values specifies the rotation of the myo on the arm (0 - logo facing down, pi - logo facing up)
     /// Only supported by FW 1.3.x and above (older firmware will always report 0 for the rotation)
@@ -361,7 +472,7 @@ extern "system"
     pub fn libmyo_event_get_gyroscope(event: libmyo_event_t, index: c_uint) -> c_float;
     /// Retrieve the pose associated with an event.
     /// Valid for libmyo_event_pose events only.
-    pub fn libmyo_event_get_pose(event: libmyo_event_t) -> Pose;
+    pub fn libmyo_event_get_pose(event: libmyo_event_t) -> c_int;
     /// Retrieve the RSSI associated with an event.
     /// Valid for libmyo_event_rssi events only.
     pub fn libmyo_event_get_rssi(event: libmyo_event_t) -> i8;
@@ -0,0 +1,125 @@
+//! Forearm-relative Orientation
+
+use {EventListener, Event, HandlerResult, MACAddress, MyoEvent, ArmSync, Arm, XDirection, Quaternion, Vec3, IntoHandlerResult};
+use {ArmSyncedEvent, ArmUnsyncedEvent, DisconnectedEvent, UnpairedEvent, OrientationEvent};
+use per_device::PerDevice;
+use std::f32::consts::PI;
+
+impl ArmSync
+{
+    /// Orientation of the forearm from an orientation of the armband:
+    /// the rotation of the armband around the arm is removed and its +x axis is turned toward the wrist,
+    /// then a left arm is mirrored to a right arm so that symmetric movements give the same orientation.
+    /// The forearm frame has +x along the forearm toward the wrist.
+    pub fn forearm_orientation(&self, orientation: Quaternion) -> Quaternion
+    {
+        let x = Vec3::new(1.0, 0.0, 0.0);
+        let mut q = orientation * Quaternion::from_axis_angle(x, -self.rotation_on_arm);
+        if self.x_direction == XDirection::TowardElbow { q = q * Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), PI); }
+        // reflection through the x-z plane: roll and yaw change their signs
+        if self.arm == Arm::Left { q = Quaternion::new(-q.x, q.y, -q.z, q.w); }
+        q.normalize()
+    }
+}
+
+/// Forearm orientation of an armband(`ForearmTracker`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForearmOrientation
+{
+    /// Timestamp of the Orientation event
+    pub timestamp: u64,
+    /// Arm of the last sync, `Arm::Unknown` while not synced
+    pub arm: Arm,
+    /// Forearm orientation, or the armband orientation while not synced
+    pub orientation: Quaternion,
+    /// Rotation around the forearm in radians
+    pub roll: f32,
+    /// Elevation of the forearm in radians(positive lowers the hand, as a rotation around y)
+    pub pitch: f32,
+    /// Heading of the forearm in radians
+    pub yaw: f32
+}
+impl ForearmOrientation
+{
+    fn new(timestamp: u64, arm: Arm, orientation: Quaternion) -> Self
+    {
+        let (roll, pitch, yaw) = orientation.euler_angles();
+        ForearmOrientation { timestamp, arm, orientation, roll, pitch, yaw }
+    }
+}
+
+/// Sync information and last orientation of an armband
+#[derive(Default)]
+struct TrackedArm { sync: Option<ArmSync>, orientation: Option<ForearmOrientation> }
+
+/// Event Listener converting the orientation of every armband to its forearm(`ArmSync::forearm_orientation`),
+/// using the sync information of the last ArmSynced event of the armband.
+/// ArmUnsynced, Disconnected and Unpaired events drop the sync information until the next sync.
+///
+/// Closures registered with `on_update` are called for each Orientation event after the orientation has been updated.
+pub struct ForearmTracker<'f> { arms: PerDevice<'f, TrackedArm, ForearmOrientation> }
+impl<'f> ForearmTracker<'f>
+{
+    pub fn new() -> Self { ForearmTracker { arms: PerDevice::new() } }
+    /// Call `handler` with each updated orientation
+    pub fn on_update<F, R>(mut self, handler: F) -> Self where F: FnMut(MACAddress, &ForearmOrientation) -> R + 'f, R: IntoHandlerResult
+    {
+        self.arms.on_output(handler);
+        self
+    }
+
+    /// Last forearm orientation of an armband
+    pub fn orientation(&self, mac_address: MACAddress) -> Option<&ForearmOrientation>
+    {
+        self.arms.get(mac_address).and_then(|a| a.orientation.as_ref())
+    }
+    /// Sync information in use for an armband
+    pub fn sync(&self, mac_address: MACAddress) -> Option<&ArmSync>
+    {
+        self.arms.get(mac_address).and_then(|a| a.sync.as_ref())
+    }
+
+    /// Apply an owned sync or Orientation event; other events are ignored
+    pub fn update(&mut self, event: &MyoEvent) -> HandlerResult
+    {
+        match *event
+        {
+            MyoEvent::ArmSynced(ref i, s) => { self.set_sync(i.mac_address, Some(s)); HandlerResult::Continue },
+            MyoEvent::ArmUnsynced(ref i) | MyoEvent::Disconnected(ref i) | MyoEvent::Unpaired(ref i) =>
+            {
+                self.set_sync(i.mac_address, None);
+                HandlerResult::Continue
+            },
+            MyoEvent::Orientation(ref i, ref d) => self.update_orientation(i.mac_address, i.timestamp, d.orientation),
+            _ => HandlerResult::Continue
+        }
+    }
+
+    fn set_sync(&mut self, mac_address: MACAddress, sync: Option<ArmSync>) { self.arms.entry(mac_address, TrackedArm::default).sync = sync; }
+    fn update_orientation(&mut self, mac_address: MACAddress, timestamp: u64, orientation: Quaternion) -> HandlerResult
+    {
+        let a = self.arms.entry(mac_address, TrackedArm::default);
+        let o = match a.sync
+        {
+            Some(ref s) => ForearmOrientation::new(timestamp, s.arm, s.forearm_orientation(orientation)),
+            None => ForearmOrientation::new(timestamp, Arm::Unknown, orientation)
+        };
+        a.orientation = Some(o);
+        self.arms.notify(mac_address, &o)
+    }
+}
+impl<'f> Default for ForearmTracker<'f>
+{
+    fn default() -> Self { Self::new() }
+}
+impl<'f> EventListener for ForearmTracker<'f>
+{
+    fn on_arm_synced(&mut self, event: ArmSyncedEvent) -> HandlerResult { self.update(&event.to_owned_event()) }
+    fn on_arm_unsynced(&mut self, event: ArmUnsyncedEvent) -> HandlerResult { self.update(&event.to_owned_event()) }
+    fn on_disconnected(&mut self, event: DisconnectedEvent) -> HandlerResult { self.update(&event.to_owned_event()) }
+    fn on_unpaired(&mut self, event: UnpairedEvent) -> HandlerResult { self.update(&event.to_owned_event()) }
+    fn on_orientation_data(&mut self, event: OrientationEvent) -> HandlerResult
+    {
+        self.update_orientation(event.mac_address(), event.timestamp(), event.q_orientation())
+    }
+}
@@ -0,0 +1,152 @@
+//! Gyroscope Bias Estimation with Stationary Detection
+
+use {EventListener, Event, HandlerResult, MACAddress, MyoEvent, Vec3, IntoHandlerResult};
+use {OrientationEvent, DisconnectedEvent, UnpairedEvent};
+use per_device::PerDevice;
+use std::collections::VecDeque;
+
+/// Parameters of a `GyroBiasEstimator`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GyroBiasConfig
+{
+    /// Number of recent samples the variances are computed over(the armband samples at 50Hz)
+    pub window: usize,
+    /// Largest gyroscope variance((deg/s)², summed over the axes) of a stationary armband
+    pub gyroscope_threshold: f32,
+    /// Largest accelerometer variance(g², summed over the axes) of a stationary armband
+    pub accelerometer_threshold: f32,
+    /// Fraction of the difference to each stationary reading the bias moves by, in 0.0 ..= 1.0
+    pub learning_rate: f32
+}
+impl Default for GyroBiasConfig
+{
+    fn default() -> Self
+    {
+        GyroBiasConfig { window: 25, gyroscope_threshold: 1.0, accelerometer_threshold: 0.0004, learning_rate: 0.02 }
+    }
+}
+
+/// Sum of the per-axis variances of vectors
+fn variance<I: Iterator<Item = Vec3> + Clone>(values: I, count: usize) -> f32
+{
+    let mean = values.clone().fold(Vec3::new(0.0, 0.0, 0.0), |a, v| a + v) * (1.0 / count as f32);
+    values.map(|v| { let d = v - mean; d.dot(d) }).sum::<f32>() / count as f32
+}
+
+/// Estimator of the gyroscope bias of an armband.
+/// The armband is stationary while the variances of both sensors over the last `window` samples are below their thresholds;
+/// meanwhile the bias follows the gyroscope readings, and it is kept as it is while the armband moves.
+#[derive(Debug, Clone)]
+pub struct GyroBiasEstimator
+{
+    config: GyroBiasConfig,
+    samples: VecDeque<(Vec3, Vec3)>,
+    bias: Vec3, stationary: bool, corrected: Vec3
+}
+impl GyroBiasEstimator
+{
+    pub fn new(config: GyroBiasConfig) -> Self
+    {
+        let zero = Vec3::new(0.0, 0.0, 0.0);
+        GyroBiasEstimator { config, samples: VecDeque::with_capacity(config.window), bias: zero, stationary: false, corrected: zero }
+    }
+    /// Add a reading of the accelerometer(g) and the gyroscope(deg/s), and return the corrected gyroscope reading
+    pub fn update(&mut self, accelerometer: Vec3, gyroscope: Vec3) -> Vec3
+    {
+        if self.samples.len() >= self.config.window.max(1) { self.samples.pop_front(); }
+        self.samples.push_back((accelerometer, gyroscope));
+        let n = self.samples.len();
+        self.stationary = n >= self.config.window &&
+            variance(self.samples.iter().map(|s| s.1), n) <= self.config.gyroscope_threshold &&
+            variance(self.samples.iter().map(|s| s.0), n) <= self.config.accelerometer_threshold;
+        if self.stationary { self.bias = self.bias + (gyroscope - self.bias) * self.config.learning_rate; }
+        self.corrected = gyroscope - self.bias;
+        self.corrected
+    }
+    /// Estimated bias(deg/s)
+    pub fn bias(&self) -> Vec3 { self.bias }
+    /// Start from a known bias, e.g. one saved from an earlier session
+    pub fn set_bias(&mut self, bias: Vec3) { self.bias = bias; }
+    /// Whether the armband was stationary at the last reading
+    pub fn is_stationary(&self) -> bool { self.stationary }
+    /// Last gyroscope reading without the bias(deg/s)
+    pub fn corrected(&self) -> Vec3 { self.corrected }
+    /// Forget the recent samples, keeping the bias
+    pub fn reset(&mut self) { self.samples.clear(); self.stationary = false; }
+}
+impl Default for GyroBiasEstimator
+{
+    fn default() -> Self { GyroBiasEstimator::new(GyroBiasConfig::default()) }
+}
+
+/// Corrected gyroscope reading of an armband(`GyroBiasTracker`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CorrectedGyro
+{
+    /// Timestamp of the Orientation event
+    pub timestamp: u64,
+    /// Gyroscope reading without the bias(deg/s)
+    pub gyroscope: Vec3,
+    /// Estimated bias(deg/s)
+    pub bias: Vec3,
+    /// Whether the armband is stationary
+    pub stationary: bool
+}
+
+/// Event Listener running a `GyroBiasEstimator` on the Orientation events of every armband.
+/// Disconnected and Unpaired events reset the recent samples of the armband; the bias is kept for its reconnection.
+pub struct GyroBiasTracker<'g>
+{
+    config: GyroBiasConfig,
+    devices: PerDevice<'g, GyroBiasEstimator, CorrectedGyro>
+}
+impl<'g> GyroBiasTracker<'g>
+{
+    pub fn new(config: GyroBiasConfig) -> Self { GyroBiasTracker { config, devices: PerDevice::new() } }
+    /// Call `handler` with each corrected reading
+    pub fn on_update<F, R>(mut self, handler: F) -> Self where F: FnMut(MACAddress, &CorrectedGyro) -> R + 'g, R: IntoHandlerResult
+    {
+        self.devices.on_output(handler);
+        self
+    }
+    /// Estimator of an armband
+    pub fn estimator(&self, mac_address: MACAddress) -> Option<&GyroBiasEstimator> { self.devices.get(mac_address) }
+    /// Whether an armband was stationary at its last reading
+    pub fn is_stationary(&self, mac_address: MACAddress) -> bool { self.estimator(mac_address).is_some_and(GyroBiasEstimator::is_stationary) }
+
+    /// Apply an owned Orientation, Disconnected or Unpaired event; other events are ignored
+    pub fn update(&mut self, event: &MyoEvent) -> HandlerResult
+    {
+        match *event
+        {
+            MyoEvent::Orientation(ref i, ref d) => self.update_readings(i.mac_address, i.timestamp, d.accelerometer, d.gyroscope),
+            MyoEvent::Disconnected(ref i) | MyoEvent::Unpaired(ref i) => { self.reset(i.mac_address); HandlerResult::Continue },
+            _ => HandlerResult::Continue
+        }
+    }
+
+    fn reset(&mut self, mac_address: MACAddress)
+    {
+        if let Some(e) = self.devices.get_mut(mac_address) { e.reset(); }
+    }
+    fn update_readings(&mut self, mac_address: MACAddress, timestamp: u64, accelerometer: Vec3, gyroscope: Vec3) -> HandlerResult
+    {
+        let config = self.config;
+        let e = self.devices.entry(mac_address, || GyroBiasEstimator::new(config));
+        let corrected = CorrectedGyro { timestamp, gyroscope: e.update(accelerometer, gyroscope), bias: e.bias(), stationary: e.is_stationary() };
+        self.devices.notify(mac_address, &corrected)
+    }
+}
+impl<'g> Default for GyroBiasTracker<'g>
+{
+    fn default() -> Self { GyroBiasTracker::new(GyroBiasConfig::default()) }
+}
+impl<'g> EventListener for GyroBiasTracker<'g>
+{
+    fn on_disconnected(&mut self, event: DisconnectedEvent) -> HandlerResult { self.reset(event.mac_address()); HandlerResult::Continue }
+    fn on_unpaired(&mut self, event: UnpairedEvent) -> HandlerResult { self.reset(event.mac_address()); HandlerResult::Continue }
+    fn on_orientation_data(&mut self, event: OrientationEvent) -> HandlerResult
+    {
+        self.update_readings(event.mac_address(), event.timestamp(), event.v_accelerometer(), event.v_gyroscope())
+    }
+}
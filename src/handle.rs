@@ -0,0 +1,97 @@
+//! Armband Handles keyed by MAC Address
+
+use {Hub, Backend, Result, Error, MACAddress, EventType, Command, VibrationType, UnlockType};
+use backend::{RawEvent, Device};
+
+struct DeviceEntry
+{
+    mac_address: MACAddress, device: Option<Box<dyn Device + Send>>, paired: bool, connected: bool
+}
+/// Devices seen by a hub, updated before each event is dispatched
+#[derive(Default)]
+pub(crate) struct DeviceTable(Vec<DeviceEntry>);
+impl DeviceTable
+{
+    fn find(&self, mac_address: MACAddress) -> Option<&DeviceEntry> { self.0.iter().find(|d| d.mac_address == mac_address) }
+
+    pub(crate) fn track(&mut self, event: &dyn RawEvent)
+    {
+        let mac_address = MACAddress(event.mac_address());
+        let t = event.event_type();
+        let index = match self.0.iter().position(|d| d.mac_address == mac_address)
+        {
+            Some(n) => n,
+            None =>
+            {
+                // events other than Paired only arrive from connected armbands
+                let connected = !matches!(t, EventType::Paired | EventType::Unpaired | EventType::Disconnected);
+                self.0.push(DeviceEntry { mac_address, device: None, paired: true, connected });
+                self.0.len() - 1
+            }
+        };
+        let d = &mut self.0[index];
+        match t
+        {
+            EventType::Paired => { d.paired = true; d.device = Some(event.device().retain()); },
+            EventType::Connected => { d.paired = true; d.connected = true; d.device = Some(event.device().retain()); },
+            EventType::Unpaired => { d.paired = false; d.connected = false; d.device = None; },
+            EventType::Disconnected => d.connected = false,
+            _ => if d.device.is_none() && d.paired { d.device = Some(event.device().retain()); }
+        }
+    }
+}
+
+/// Handle of an armband identified by its MAC address(`Hub::armband`).
+/// Unlike `Armband`, it can be kept between runs and stays usable across reconnects of the armband;
+/// commands fail with a descriptive error while the armband is not connected.
+/// An armband first seen through an event other than Paired, Unpaired or Disconnected counts as paired and connected.
+pub struct ArmbandHandle<'h, B: Backend + 'h>
+{
+    hub: &'h Hub<B>, mac_address: MACAddress
+}
+impl<'h, B: Backend + 'h> ArmbandHandle<'h, B>
+{
+    /// MAC Address
+    pub fn mac_address(&self) -> MACAddress { self.mac_address }
+    /// Whether the armband is paired with the hub
+    pub fn is_paired(&self) -> bool { self.hub.devices.borrow().find(self.mac_address).is_some_and(|d| d.paired) }
+    /// Whether the armband is connected to the hub
+    pub fn is_connected(&self) -> bool { self.hub.devices.borrow().find(self.mac_address).is_some_and(|d| d.connected) }
+
+    /// Execute a command
+    pub fn execute(&self, command: Command) -> Result<()>
+    {
+        let table = self.hub.devices.borrow();
+        let gone = |reason: &str| Err(Error::DeviceGone { mac_address: self.mac_address, reason: reason.to_owned() });
+        match table.find(self.mac_address)
+        {
+            None => gone("has not been seen by the hub"),
+            Some(&DeviceEntry { paired: false, .. }) | Some(&DeviceEntry { device: None, .. }) => gone("has been unpaired"),
+            Some(&DeviceEntry { connected: false, .. }) => gone("is disconnected"),
+            Some(&DeviceEntry { device: Some(ref d), .. }) => d.execute(command)
+        }
+    }
+    DefArmbandCommands!(Result<()>);
+}
+impl<'h, B: Backend + 'h> Clone for ArmbandHandle<'h, B>
+{
+    fn clone(&self) -> Self { ArmbandHandle { hub: self.hub, mac_address: self.mac_address } }
+}
+impl<'h, B: Backend + 'h> std::fmt::Debug for ArmbandHandle<'h, B>
+{
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        fmt.debug_tuple("ArmbandHandle").field(&self.mac_address).finish()
+    }
+}
+
+impl<B: Backend> Hub<B>
+{
+    /// Handle of the armband with `mac_address`, which need not be known to the hub yet
+    pub fn armband(&self, mac_address: MACAddress) -> ArmbandHandle<'_, B> { ArmbandHandle { hub: self, mac_address } }
+    /// Handles of the armbands currently paired with the hub
+    pub fn armbands(&self) -> Vec<ArmbandHandle<'_, B>>
+    {
+        self.devices.borrow().0.iter().filter(|d| d.paired).map(|d| self.armband(d.mac_address)).collect()
+    }
+}
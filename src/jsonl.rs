@@ -0,0 +1,120 @@
+//! JSON Lines Event Log(`jsonl` feature)
+//!
+//! Each line is an object with `type`(e.g. `"arm_synced"`), `timestamp`(microseconds), `mac`(`"01-02-03-04-05-06"`) and `name`,
+//! plus the fields of its type:
+//! `firmware`(`{"major", "minor", "patch", "hardware_revision"}`) for paired/connected,
+//! `arm`, `x_direction`, `warmup_state` and `rotation_on_arm` for arm_synced,
+//! `orientation`(`{"x", "y", "z", "w"}`), `accelerometer` and `gyroscope`(`[x, y, z]`) for orientation,
+//! `pose`, `rssi`, `emg`(8 values), `battery_level` and `warmup_result` for the event types of the same names.
+//! Enum values are written as lowercase names such as `"toward_wrist"` or `"wave_in"`.
+//! Each object is the `serde` form of a `MyoEvent`, so `serde_json` reads and writes the same lines.
+
+use {EventListener, EventSink, Event, HandlerResult, Result, Error, MyoEvent};
+use {PairedEvent, UnpairedEvent, ConnectedEvent, DisconnectedEvent, ArmSyncedEvent, ArmUnsyncedEvent, OrientationEvent,
+    PoseEvent, RSSIEvent, UnlockedEvent, LockedEvent, EMGEvent, BatteryLevelEvent, WarmupCompletedEvent};
+use serde::Deserialize;
+use serde_json::{self, Value};
+use std::io::{self, Write, BufRead, BufReader, BufWriter};
+use std::fs::File;
+use std::path::Path;
+
+impl MyoEvent
+{
+    /// Log object of the event(`jsonl` feature)
+    pub fn to_json(&self) -> Value
+    {
+        serde_json::to_value(self).expect("events serialize to objects")
+    }
+    /// Event of a log object(`jsonl` feature)
+    pub fn from_json(value: &Value) -> Result<Self>
+    {
+        MyoEvent::deserialize(value).map_err(|e| Error::InvalidArgument(e.to_string()))
+    }
+}
+
+/// Event Listener writing one JSON object per event(`jsonl` feature).
+/// Write errors stop the run and are kept in `error`.
+pub struct JsonlWriter<W: Write = BufWriter<File>> { writer: W, error: Option<io::Error> }
+impl JsonlWriter
+{
+    /// Create a log file, replacing an existing one
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> { Ok(JsonlWriter::new(BufWriter::new(File::create(path)?))) }
+}
+impl<W: Write> JsonlWriter<W>
+{
+    /// Log to `writer`
+    pub fn new(writer: W) -> Self { JsonlWriter { writer, error: None } }
+    /// Append an event
+    pub fn write_event(&mut self, event: &MyoEvent) -> io::Result<()>
+    {
+        serde_json::to_writer(&mut self.writer, event)?;
+        self.writer.write_all(b"\n")
+    }
+    /// Write buffered lines
+    pub fn flush(&mut self) -> io::Result<()> { self.writer.flush() }
+    /// First write error that stopped the log as a listener
+    pub fn error(&self) -> Option<&io::Error> { self.error.as_ref() }
+    /// Flush and take back the writer
+    pub fn finish(mut self) -> io::Result<W>
+    {
+        if let Some(e) = self.error.take() { return Err(e); }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn log<E: Event>(&mut self, event: &E) -> HandlerResult
+    {
+        if self.error.is_some() { return HandlerResult::Stop; }
+        match self.write_event(&event.to_owned_event())
+        {
+            Ok(()) => HandlerResult::Continue,
+            Err(e) => { self.error = Some(e); HandlerResult::Stop }
+        }
+    }
+}
+macro_rules! DefJsonlListener
+{
+    ($($handler: ident($ev: ident) = $_t: ident),*) =>
+    {
+        impl<W: Write> EventListener for JsonlWriter<W>
+        {
+            $(fn $handler(&mut self, event: $ev) -> HandlerResult { self.log(&event) })*
+        }
+    }
+}
+ForEachEventHandler!(DefJsonlListener);
+
+/// Reader of a JSON Lines log, iterating over its events(`jsonl` feature).
+/// Blank lines are skipped; malformed lines are reported as `InvalidData` errors.
+pub struct JsonlReader<R: BufRead = BufReader<File>>(io::Lines<R>);
+impl JsonlReader
+{
+    /// Open a log file
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> { Ok(JsonlReader::new(BufReader::new(File::open(path)?))) }
+}
+impl<R: BufRead> JsonlReader<R>
+{
+    /// Read a log from `reader`
+    pub fn new(reader: R) -> Self { JsonlReader(reader.lines()) }
+}
+impl<R: BufRead> Iterator for JsonlReader<R>
+{
+    type Item = io::Result<MyoEvent>;
+    fn next(&mut self) -> Option<io::Result<MyoEvent>>
+    {
+        loop
+        {
+            let line = match self.0.next()? { Ok(l) => l, Err(e) => return Some(Err(e)) };
+            if line.trim().is_empty() { continue; }
+            return Some(serde_json::from_str(&line).map_err(io::Error::from));
+        }
+    }
+}
+impl<W: Write> EventSink for JsonlWriter<W>
+{
+    fn flush(&mut self) -> io::Result<()>
+    {
+        if let Some(ref e) = self.error { return Err(io::Error::new(e.kind(), e.to_string())); }
+        JsonlWriter::flush(self)
+    }
+}
@@ -1,32 +1,173 @@
 //! Myo Armband
 
 extern crate libc;
+#[cfg(feature = "dynamic")] extern crate libloading;
+#[cfg(feature = "stream")] extern crate futures_core;
+#[cfg(feature = "stream")] extern crate futures_channel;
+#[cfg(feature = "jsonl")] extern crate serde_json;
+#[cfg(feature = "serde")] #[macro_use] extern crate serde;
+
+/// Invoke `$m!` with `$args` followed by the list of `EventListener` handlers: `handler(EventStruct) = EventType`
+macro_rules! ForEachEventHandler
+{
+    ($m: ident $($args: tt)*) =>
+    {
+        $m!
+        {
+            $($args)*
+            on_paired(PairedEvent) = Paired, on_unpaired(UnpairedEvent) = Unpaired,
+            on_connected(ConnectedEvent) = Connected, on_disconnected(DisconnectedEvent) = Disconnected,
+            on_arm_synced(ArmSyncedEvent) = ArmSynced, on_arm_unsynced(ArmUnsyncedEvent) = ArmUnsynced,
+            on_orientation_data(OrientationEvent) = Orientation, on_pose(PoseEvent) = Pose,
+            on_rssi_value(RSSIEvent) = RSSI, on_unlocked(UnlockedEvent) = Unlocked, on_locked(LockedEvent) = Locked,
+            on_emg_data(EMGEvent) = EMG, on_battery_level(BatteryLevelEvent) = BatteryLevel,
+            on_warmup_completed(WarmupCompletedEvent) = WarmupCompleted
+        }
+    }
+}
+
+/// Wrapper Macros
+macro_rules! DefWrapperFunc
+{
+    ($(#[$m: meta])* pub fn $ename: ident ( $($narg: ident : $targ: ty),* ) -> $rtype: ty = Command::$($cmd: tt)+) =>
+    {
+        $(#[$m])* pub fn $ename(&self $(, $narg: $targ)*) -> $rtype { self.execute(Command::$($cmd)+) }
+    };
+    ($(#[$m: meta])* pub fn $ename: ident ( $($narg: ident : $targ: ty),* ) -> $rtype: ty = $fname: ident ( $($farg: expr),* )) =>
+    {
+        $(#[$m])* pub fn $ename(&self $(, $narg: $targ)*) -> $rtype { self.0.$fname($($farg),*) as _ }
+    }
+}
+
+/// Command wrappers of `Armband`, `ArmbandHandle` and `QueuedArmband`, calling `self.execute`
+macro_rules! DefArmbandCommands
+{
+    ($r: ty) =>
+    {
+        DefWrapperFunc!(/// Vibrate
+            pub fn vibrate(vtype: VibrationType) -> $r = Command::Vibrate(vtype));
+        DefWrapperFunc!(/// Asynchronous Request: RSSI
+            pub fn request_rssi() -> $r = Command::RequestRSSI);
+        DefWrapperFunc!(/// Asynchronous Request: Battery Level
+            pub fn request_battery_level() -> $r = Command::RequestBatteryLevel);
+        DefWrapperFunc!(/// Stream EMG Data
+            pub fn set_stream_emg(stream_emg_data: bool) -> $r = Command::SetStreamEMG(stream_emg_data));
+
+        // Locking Mechanism
+        DefWrapperFunc!(/// Lock Armband
+            pub fn lock() -> $r = Command::Lock);
+        DefWrapperFunc!(/// Unlock Armband
+            pub fn unlock(unlock_type: UnlockType) -> $r = Command::Unlock(unlock_type));
+        DefWrapperFunc!(/// Notify Myo device that a user action was recognized.
+            /// Device will vibrate.
+            pub fn notify_user_action() -> $r = Command::NotifyUserAction);
+    }
+}
 
 mod ffi;
+mod error;
+mod backend;
+mod libmyo;
+mod owned;
+mod polling;
+mod listener;
+mod registry;
+mod handle;
+mod commands;
+mod recording;
+mod replay;
+mod csv;
+mod math;
+mod per_device;
+mod forearm;
+mod calibration;
+mod ahrs;
+mod gyro_bias;
+#[cfg(feature = "serde")] mod names;
+#[cfg(feature = "serde")] mod serialization;
+#[cfg(feature = "jsonl")] mod jsonl;
+#[cfg(feature = "stream")] mod stream;
 pub use ffi::{
     ResultCode, LockingPolicy, VibrationType, UnlockType, VersionComponent, EventType,
     Arm, XDirection, WarmupState, WarmupResult, Pose, OrientationIndex, HandlerResult,
     HardwareRevision
 };
-use std::ffi::CStr;
+pub use error::Error;
+pub use backend::{Backend, BackendHandler, RawEvent, Device, Command};
+pub use libmyo::LibmyoBackend;
+pub use owned::{MyoEvent, EventInfo, FirmwareVersion, ArmSync, OrientationData, OwnedRawEvent};
+pub use polling::{Events, HubThread};
+pub use listener::{Listener, IntoHandlerResult, Filter, EventPredicate, Map, EventSink};
+pub use registry::{DeviceRegistry, DeviceState, DeviceChange};
+pub use handle::ArmbandHandle;
+pub use commands::{CommandQueue, QueuedArmband, CommandResult};
+pub use recording::{Recorder, RecordingReader, RecordingHeader, RecordedDevice, FORMAT_VERSION};
+pub use replay::{ReplayBackend, ReplaySpeed};
+pub use csv::{CsvWriter, CsvConfig, TimestampUnit};
+pub use math::{Quaternion, Vec3};
+pub use forearm::{ForearmTracker, ForearmOrientation};
+pub use calibration::{Calibrator, RelativeOrientation};
+pub use ahrs::{AhrsFilter, Madgwick, Mahony, SensorFusion, FusedOrientation};
+pub use gyro_bias::{GyroBiasConfig, GyroBiasEstimator, GyroBiasTracker, CorrectedGyro};
+#[cfg(feature = "jsonl")] pub use jsonl::{JsonlWriter, JsonlReader};
+use handle::DeviceTable;
+use commands::CommandChannel;
+#[cfg(feature = "stream")] pub use stream::{HubStream, StreamConfig, DropPolicy, StreamCommander, CommandReply};
+use std::ffi::{CStr, CString};
+use std::cell::RefCell;
 
 /// Operation Result
-pub type Result<T> = std::result::Result<T, ErrorDetails>;
+pub type Result<T> = std::result::Result<T, Error>;
+/// Former name of `Error`
+#[deprecated(note = "use `myo::Error`")]
+pub type ErrorDetails = Error;
+
+/// Load libmyo from `path` at runtime. Has no effect if libmyo has already been loaded.
+/// Without an explicit call, `Hub::init` loads the library named by `MYO_LIBRARY` environment variable,
+/// or `myo64`/`myo32` from the system search path.
+#[cfg(feature = "dynamic")]
+pub fn load_library<P: AsRef<std::ffi::OsStr>>(path: P) -> Result<()>
+{
+    ffi::load(path.as_ref()).map_err(Error::LibraryMissing)
+}
+
+/// Build a CString, truncating at the first interior nul byte
+fn cstring_lossy<S: Into<Vec<u8>>>(s: S) -> CString
+{
+    let mut bytes = s.into();
+    if let Some(p) = bytes.iter().position(|&b| b == 0) { bytes.truncate(p); }
+    CString::new(bytes).unwrap()
+}
 
+enum MyoStringRepr { Libmyo(ffi::libmyo_string_t), Owned(CString) }
 /// Owned Myo String
-pub struct MyoString(ffi::libmyo_string_t);
+pub struct MyoString(MyoStringRepr);
 impl MyoString
 {
+    pub(crate) fn libmyo(s: ffi::libmyo_string_t) -> Self { MyoString(MyoStringRepr::Libmyo(s)) }
+
     pub fn c_str(&self) -> &CStr
     {
-        unsafe { CStr::from_ptr(ffi::libmyo_string_c_str(self.0)) }
+        match self.0
+        {
+            MyoStringRepr::Libmyo(s) => unsafe { CStr::from_ptr(ffi::libmyo_string_c_str(s)) },
+            MyoStringRepr::Owned(ref s) => s
+        }
     }
 }
+impl<'a> From<&'a str> for MyoString
+{
+    fn from(s: &'a str) -> Self { MyoString(MyoStringRepr::Owned(cstring_lossy(s))) }
+}
+impl From<String> for MyoString
+{
+    fn from(s: String) -> Self { MyoString(MyoStringRepr::Owned(cstring_lossy(s))) }
+}
 impl Drop for MyoString
 {
     fn drop(&mut self)
     {
-        unsafe { ffi::libmyo_string_free(self.0) };
+        if let MyoStringRepr::Libmyo(s) = self.0 { unsafe { ffi::libmyo_string_free(s) }; }
     }
 }
 impl std::fmt::Debug for MyoString
@@ -37,74 +178,147 @@ impl std::fmt::Debug for MyoString
     }
 }
 
-/// Owned Error Details
-pub struct ErrorDetails(ffi::libmyo_error_details_t);
-impl ErrorDetails
+/// MAC Address(48 bits).
+/// Displayed as `01-02-03-04-05-06` like libmyo, parsed from that form, `01:02:03:04:05:06`, `0102.0304.0506` or `010203040506`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct MACAddress(u64);
+impl MACAddress
 {
-    pub fn message(&self) -> &CStr
+    /// Largest valid MAC address value
+    pub const MAX: u64 = 0xffff_ffff_ffff;
+
+    /// MAC address of a 48-bit value, or `None` if `value` exceeds `MACAddress::MAX`
+    pub fn new(value: u64) -> Option<Self> { if value <= Self::MAX { Some(MACAddress(value)) } else { None } }
+    /// MAC address of 6 bytes, most significant first
+    pub fn from_bytes(bytes: [u8; 6]) -> Self { MACAddress(bytes.iter().fold(0, |a, &b| a << 8 | b as u64)) }
+    /// 6 bytes, most significant first
+    pub fn bytes(&self) -> [u8; 6]
     {
-        unsafe { CStr::from_ptr(ffi::libmyo_error_cstring(self.0)) }
+        let mut bytes = [0; 6];
+        for (n, b) in bytes.iter_mut().enumerate() { *b = (self.0 >> ((5 - n) * 8)) as u8; }
+        bytes
     }
-    pub fn kind(&self) -> ResultCode
+    /// 48-bit value
+    pub fn as_u64(&self) -> u64 { self.0 }
+    /// Lowercase form with bytes joined by `separator`, e.g. `":"` or `""`
+    pub fn display<'s>(&self, separator: &'s str) -> impl std::fmt::Display + 's
     {
-        unsafe { ffi::libmyo_error_kind(self.0) }
+        struct Separated<'s>([u8; 6], &'s str);
+        impl<'s> std::fmt::Display for Separated<'s>
+        {
+            fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result
+            {
+                for (n, b) in self.0.iter().enumerate()
+                {
+                    if n > 0 { fmt.write_str(self.1)?; }
+                    write!(fmt, "{:02x}", b)?;
+                }
+                Ok(())
+            }
+        }
+        Separated(self.bytes(), separator)
     }
 }
-impl Drop for ErrorDetails
+impl std::fmt::Display for MACAddress
 {
-    fn drop(&mut self)
-    {
-        unsafe { ffi::libmyo_free_error_details(self.0) };
-    }
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result { self.display("-").fmt(fmt) }
 }
-impl std::fmt::Debug for ErrorDetails
+impl std::str::FromStr for MACAddress
 {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self>
     {
-        write!(fmt, "libmyo Error Details({:?}: {:?})", self.kind(), self.message())
+        let invalid = || Error::InvalidArgument(format!("invalid MAC address {:?}", s));
+        let (group, separator) = match s.len()
+        {
+            17 => (2, s.chars().nth(2).filter(|&c| c == '-' || c == ':').ok_or_else(invalid)?),
+            14 => (4, '.'),
+            12 => (12, ' '),
+            _ => return Err(invalid())
+        };
+        let mut digits = String::with_capacity(12);
+        for (n, c) in s.chars().enumerate()
+        {
+            if n % (group + 1) == group { if c != separator { return Err(invalid()); } }
+            else if c.is_ascii_hexdigit() { digits.push(c); }
+            else { return Err(invalid()); }
+        }
+        u64::from_str_radix(&digits, 16).map(MACAddress).map_err(|_| invalid())
     }
 }
-impl std::fmt::Display for ErrorDetails
+impl From<[u8; 6]> for MACAddress
 {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result
-    {
-        std::fmt::Debug::fmt(self, fmt)
-    }
+    fn from(bytes: [u8; 6]) -> Self { MACAddress::from_bytes(bytes) }
 }
-impl std::error::Error for ErrorDetails
+impl From<MACAddress> for [u8; 6]
 {
-    fn description(&self) -> &str { "libmyo Error" }
+    fn from(mac_address: MACAddress) -> Self { mac_address.bytes() }
 }
-
-/// MAC Address
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct MACAddress(u64);
-impl std::fmt::Display for MACAddress
+impl From<MACAddress> for u64
 {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result
+    fn from(mac_address: MACAddress) -> Self { mac_address.0 }
+}
+impl std::convert::TryFrom<u64> for MACAddress
+{
+    type Error = Error;
+    fn try_from(value: u64) -> Result<Self>
     {
-        write!(fmt, "{}", MyoString(unsafe { ffi::libmyo_mac_address_to_string(self.0) }).c_str().to_str().unwrap())
+        MACAddress::new(value).ok_or_else(|| Error::InvalidArgument(format!("MAC address {:#x} exceeds 48 bits", value)))
     }
 }
 
-/// Wrapper Macros
-macro_rules! DefWrapperFunc
+#[cfg(test)]
+mod mac_address_tests
 {
-    (pub fn $ename: ident ( $($narg: ident : $targ: ty),* ) = $fname: ident ( $($farg: expr),* )) =>
+    use super::{MACAddress, ResultCode};
+    use std::convert::TryFrom;
+
+    fn sample() -> MACAddress { MACAddress::from_bytes([0x01, 0x02, 0x03, 0xab, 0xcd, 0xef]) }
+
+    #[test]
+    fn formats_with_any_separator()
     {
-        pub fn $ename(&self $(, $narg: $targ)*) -> Result<()>
+        assert_eq!(sample().to_string(), "01-02-03-ab-cd-ef");
+        assert_eq!(sample().display(":").to_string(), "01:02:03:ab:cd:ef");
+        assert_eq!(sample().display("").to_string(), "010203abcdef");
+        assert_eq!(MACAddress::from_bytes([0; 6]).to_string(), "00-00-00-00-00-00");
+    }
+
+    #[test]
+    fn parses_every_supported_form()
+    {
+        for s in &["01-02-03-ab-cd-ef", "01-02-03-AB-CD-EF", "01:02:03:ab:cd:ef", "01:02:03:AB:CD:EF",
+            "0102.03ab.cdef", "0102.03AB.CDEF", "010203abcdef", "010203ABCDEF"]
         {
-            let mut e = std::ptr::null_mut();
-            let r = unsafe { ffi::$fname(self.0 $(, $farg)*, &mut e) };
-            if r == ResultCode::Success { Ok(()) } else { Err(ErrorDetails(e)) }
+            assert_eq!(s.parse::<MACAddress>().unwrap(), sample(), "{}", s);
         }
-    };
-    (pub fn $ename: ident ( $($narg: ident : $targ: ty),* ) -> $rtype: ty = $fname: ident ( $($farg: expr),* )) =>
+        assert_eq!("ff:ff:ff:ff:ff:ff".parse::<MACAddress>().unwrap().as_u64(), MACAddress::MAX);
+        for s in &["01-02-03-ab-cd", "01-02:03-ab-cd-ef", "01.02.03.ab.cd.ef", "01-02-03-ab-cd-eg", "0102-03ab-cdef",
+            "010.203ab.cdef", "+10203abcdef", "01 02 03 ab cd ef", "0010203abcdef", ""]
+        {
+            assert_eq!(s.parse::<MACAddress>().unwrap_err().kind(), ResultCode::ErrorInvalidArgument, "{}", s);
+        }
+    }
+
+    #[test]
+    fn converts_and_rejects_values_beyond_48_bits()
     {
-        pub fn $ename(&self $(, $narg: $targ)*) -> $rtype
+        let mac_address = sample();
+        assert_eq!(<[u8; 6]>::from(mac_address), [0x01, 0x02, 0x03, 0xab, 0xcd, 0xef]);
+        assert_eq!(MACAddress::from(mac_address.bytes()), mac_address);
+        assert_eq!(u64::from(mac_address), 0x010203abcdef);
+        assert_eq!(MACAddress::try_from(0x010203abcdef).unwrap(), mac_address);
+        assert_eq!(MACAddress::new(MACAddress::MAX).unwrap().to_string(), "ff-ff-ff-ff-ff-ff");
+        for &v in &[MACAddress::MAX + 1, 1 << 48 | 0x010203abcdef, u64::MAX]
         {
-            unsafe { ffi::$fname(self.0 $(, $farg)*) as _ }
+            assert!(MACAddress::new(v).is_none());
+            assert_eq!(MACAddress::try_from(v).unwrap_err().kind(), ResultCode::ErrorInvalidArgument);
         }
+
+        let mut sorted = [mac_address, MACAddress::from_bytes([0; 6]), MACAddress::new(MACAddress::MAX).unwrap()];
+        sorted.sort();
+        assert_eq!(sorted.iter().map(MACAddress::as_u64).collect::<Vec<_>>(), vec![0, 0x010203abcdef, MACAddress::MAX]);
+        assert_eq!(sorted.iter().cloned().collect::<std::collections::HashSet<_>>().len(), 3);
     }
 }
 
@@ -112,206 +326,221 @@ macro_rules! DefWrapperFunc
 pub trait EventListener
 {
     /// Called when successfully paired with a Myo.
-    fn on_paired(&mut self, event: PairedEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_paired(&mut self, _event: PairedEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when successfully unpaired from a Myo.
-    fn on_unpaired(&mut self, event: UnpairedEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_unpaired(&mut self, _event: UnpairedEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when a Myo has successfully connected.
-    fn on_connected(&mut self, event: ConnectedEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_connected(&mut self, _event: ConnectedEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when a Myo has been disconnected.
-    fn on_disconnected(&mut self, event: DisconnectedEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_disconnected(&mut self, _event: DisconnectedEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when a Myo has recognized that the sync gesture has been successfully performed.
-    fn on_arm_synced(&mut self, event: ArmSyncedEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_arm_synced(&mut self, _event: ArmSyncedEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when a Myo has been moved or removed from the arm.
-    fn on_arm_unsynced(&mut self, event: ArmUnsyncedEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_arm_unsynced(&mut self, _event: ArmUnsyncedEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when orientation data has been received.
-    fn on_orientation_data(&mut self, event: OrientationEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_orientation_data(&mut self, _event: OrientationEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when a change in pose has been detected.
-    fn on_pose(&mut self, event: PoseEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_pose(&mut self, _event: PoseEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when an RSSI value has been received.
-    fn on_rssi_value(&mut self, event: RSSIEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_rssi_value(&mut self, _event: RSSIEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when a Myo has become unlocked.
-    fn on_unlocked(&mut self, event: UnlockedEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_unlocked(&mut self, _event: UnlockedEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when a Myo has become locked.
-    fn on_locked(&mut self, event: LockedEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_locked(&mut self, _event: LockedEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when EMG data has been received.
-    fn on_emg_data(&mut self, event: EMGEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_emg_data(&mut self, _event: EMGEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when a battery level value has been received.
-    fn on_battery_level(&mut self, event: BatteryLevelEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_battery_level(&mut self, _event: BatteryLevelEvent) -> HandlerResult { HandlerResult::Continue }
     /// Called when the warmup period has completed.
-    fn on_warmup_completed(&mut self, event: WarmupCompletedEvent) -> HandlerResult { HandlerResult::Continue }
+    fn on_warmup_completed(&mut self, _event: WarmupCompletedEvent) -> HandlerResult { HandlerResult::Continue }
+}
+
+impl HandlerResult
+{
+    /// `Stop` if either of the results is `Stop`
+    pub fn combine(self, other: HandlerResult) -> HandlerResult
+    {
+        if self == HandlerResult::Stop || other == HandlerResult::Stop { HandlerResult::Stop } else { HandlerResult::Continue }
+    }
 }
 
-/// Store for Trait Object(stable passing for std::raw::TraitObject)
-struct TraitObjectStore<'a, T: 'a + ?Sized>(&'a mut T);
+/// Event sinks attached to a hub, and the ones attached while an event is dispatched to them
+#[derive(Default)]
+struct Sinks { attached: RefCell<Vec<Box<dyn EventSink + Send>>>, pending: RefCell<Vec<Box<dyn EventSink + Send>>> }
 
-/// Hub
-pub struct Hub(ffi::libmyo_hub_t);
+/// Hub.
+/// Dropping it shuts the backend down and only logs a failure; use `shutdown` to handle the error.
+pub struct Hub<B: Backend = LibmyoBackend>
+{
+    backend: B, devices: RefCell<DeviceTable>, commands: CommandChannel, sinks: Sinks
+}
 impl Hub
 {
     /// Initialize
     pub fn init<AppID: Into<Vec<u8>>>(application_identifier: AppID) -> Result<Self>
     {
-        let (mut h, mut e) = (std::ptr::null_mut(), std::ptr::null_mut());
-        let appid = std::ffi::CString::new(application_identifier).unwrap();
-        let r = unsafe { ffi::libmyo_init_hub(&mut h, appid.as_ptr(), &mut e) };
-        if r == ResultCode::Success { Ok(Hub(h)) } else { Err(ErrorDetails(e)) }
+        LibmyoBackend::init(application_identifier).map(Hub::with_backend)
     }
-
-    DefWrapperFunc!(pub fn set_locking_policy(locking_policy: LockingPolicy) = libmyo_set_locking_policy(locking_policy));
-    /// Process Events and call the provided callback as they occur
-    pub fn run(&self, duration_ms: u32, listener: &mut EventListener) -> Result<()>
+}
+impl<B: Backend> Hub<B>
+{
+    /// Initialize with a custom backend
+    pub fn with_backend(backend: B) -> Self
     {
-        let mut to = TraitObjectStore(listener);
-        let mut e = std::ptr::null_mut();
-        let r = unsafe { ffi::libmyo_run(self.0, duration_ms as _, Self::run_internal_handler, &mut to as *mut TraitObjectStore<_> as *mut libc::c_void, &mut e) };
-        if r == ResultCode::Success { Ok(()) } else { Err(ErrorDetails(e)) }
+        Hub { backend, devices: RefCell::new(DeviceTable::default()), commands: CommandChannel::default(), sinks: Sinks::default() }
     }
+    /// Backend
+    pub fn backend(&self) -> &B { &self.backend }
 
-    extern "system" fn run_internal_handler(elptr: *mut libc::c_void, event: ffi::libmyo_event_t) -> HandlerResult
+    /// Set the locking policy for Myos connected to the hub.
+    pub fn set_locking_policy(&self, locking_policy: LockingPolicy) -> Result<()>
+    {
+        self.backend.set_locking_policy(locking_policy)
+    }
+    /// Apply the queued commands, then process Events and call the provided callback as they occur.
+    /// A panic of `listener` stops processing and is resumed by `run` once the backend has returned.
+    pub fn run(&self, duration_ms: u32, listener: &mut dyn EventListener) -> Result<()>
     {
-        let el: &mut EventListener = unsafe { std::mem::transmute::<_, &mut TraitObjectStore<'static, EventListener>>(&mut *elptr).0 };
-        match unsafe { std::mem::transmute(ffi::libmyo_event_get_type(event)) }
+        self.apply_commands();
+        self.backend.run(duration_ms, &mut |e|
         {
-            EventType::Paired => el.on_paired(PairedEvent(event)),
-            EventType::Unpaired => el.on_unpaired(UnpairedEvent(event)),
-            EventType::Connected => el.on_connected(ConnectedEvent(event)),
-            EventType::Disconnected => el.on_disconnected(DisconnectedEvent(event)),
-            EventType::ArmSynced => el.on_arm_synced(ArmSyncedEvent(event)),
-            EventType::ArmUnsynced => el.on_arm_unsynced(ArmUnsyncedEvent(event)),
-            EventType::Orientation => el.on_orientation_data(OrientationEvent(event)),
-            EventType::Pose => el.on_pose(PoseEvent(event)),
-            EventType::RSSI => el.on_rssi_value(RSSIEvent(event)),
-            EventType::Unlocked => el.on_unlocked(UnlockedEvent(event)),
-            EventType::Locked => el.on_locked(LockedEvent(event)),
-            EventType::EMG => el.on_emg_data(EMGEvent(event)),
-            EventType::BatteryLevel => el.on_battery_level(BatteryLevelEvent(event)),
-            EventType::WarmupCompleted => el.on_warmup_completed(WarmupCompletedEvent(event))
-        }
+            self.devices.borrow_mut().track(e);
+            {
+                let mut sinks = self.sinks.attached.borrow_mut();
+                sinks.append(&mut self.sinks.pending.borrow_mut());
+                for s in sinks.iter_mut() { dispatch(&mut **s, e); }
+            }
+            dispatch(listener, e)
+        })
+    }
+    /// Pass every event processed by `run` to `sink` as well, until the hub is shut down.
+    /// Sinks see events before the listener of `run`, and their `HandlerResult` is ignored.
+    /// A sink attached by a listener during `run` starts with the next event.
+    pub fn attach<S: EventSink + Send + 'static>(&self, sink: S) { self.sinks.pending.borrow_mut().push(Box::new(sink)); }
+    /// Flush the attached sinks and shut the backend down, reporting the first error.
+    /// Commands still queued fail with an error.
+    pub fn shutdown(mut self) -> Result<()>
+    {
+        let sinks = self.sinks.attached.get_mut();
+        sinks.append(self.sinks.pending.get_mut());
+        let flushed = sinks.iter_mut().map(|s| s.flush()).fold(Ok(()), std::io::Result::and)
+            .map_err(|e| Error::Runtime(format!("failed to flush an attached sink: {}", e)));
+        let shutdown = self.backend.shutdown();
+        flushed.and(shutdown)
     }
 }
-impl Drop for Hub
+
+/// Call the handler of `listener` matching the type of a raw event
+pub fn dispatch(el: &mut dyn EventListener, event: &dyn RawEvent) -> HandlerResult
 {
-    /// Shutdown
-    fn drop(&mut self)
+    match event.event_type()
     {
-        let mut e = std::ptr::null_mut();
-        let r = unsafe { ffi::libmyo_shutdown_hub(self.0, &mut e) };
-        if r != ResultCode::Success
-        {
-            panic!("Error on drop: {:?}", ErrorDetails(e).message());
-        }
+        EventType::Paired => el.on_paired(PairedEvent(event)),
+        EventType::Unpaired => el.on_unpaired(UnpairedEvent(event)),
+        EventType::Connected => el.on_connected(ConnectedEvent(event)),
+        EventType::Disconnected => el.on_disconnected(DisconnectedEvent(event)),
+        EventType::ArmSynced => el.on_arm_synced(ArmSyncedEvent(event)),
+        EventType::ArmUnsynced => el.on_arm_unsynced(ArmUnsyncedEvent(event)),
+        EventType::Orientation => el.on_orientation_data(OrientationEvent(event)),
+        EventType::Pose => el.on_pose(PoseEvent(event)),
+        EventType::RSSI => el.on_rssi_value(RSSIEvent(event)),
+        EventType::Unlocked => el.on_unlocked(UnlockedEvent(event)),
+        EventType::Locked => el.on_locked(LockedEvent(event)),
+        EventType::EMG => el.on_emg_data(EMGEvent(event)),
+        EventType::BatteryLevel => el.on_battery_level(BatteryLevelEvent(event)),
+        EventType::WarmupCompleted => el.on_warmup_completed(WarmupCompletedEvent(event))
     }
 }
 
-/// Myo Armband
-#[derive(Debug)]
-pub struct Armband(ffi::libmyo_myo_t);
-impl Armband
+/// Myo Armband, valid during the callback of the event it was obtained from(see `ArmbandHandle` for a lasting handle)
+pub struct Armband<'a>(&'a dyn Device);
+impl<'a> Armband<'a>
 {
     /// Raw ID
-    pub fn raw_id(&self) -> usize { unsafe { std::mem::transmute(self.0) } }
-    /// Vibrate
-    DefWrapperFunc!(pub fn vibrate(vtype: VibrationType) = libmyo_vibrate(vtype));
-    /// Asynchronous Request: RSSI
-    DefWrapperFunc!(pub fn request_rssi() = libmyo_request_rssi());
-    /// Asynchronous Request: Battery Level
-    DefWrapperFunc!(pub fn request_battery_level() = libmyo_request_battery_level());
-    /// Stream EMG Data
-    DefWrapperFunc!(pub fn set_stream_emg(stream_emg_data: bool) =
-        libmyo_set_stream_emg(if stream_emg_data { ffi::EMGStreamingMode::Enabled } else { ffi::EMGStreamingMode::Disabled }));
-
-    // Locking Mechanism
-    /// Lock Armband
-    DefWrapperFunc!(pub fn lock() = libmyo_myo_lock());
-    /// Unlock Armband
-    DefWrapperFunc!(pub fn unlock(unlock_type: UnlockType) = libmyo_myo_unlock(unlock_type));
-    /// Notify Myo device that a user action was recognized.
-    /// Device will vibrate.
-    DefWrapperFunc!(pub fn notify_user_action() = libmyo_myo_notify_user_action(ffi::UserActionType::Single));
+    pub fn raw_id(&self) -> usize { self.0.raw_id() }
+    /// Execute a command
+    pub fn execute(&self, command: Command) -> Result<()> { self.0.execute(command) }
+    DefArmbandCommands!(Result<()>);
+}
+impl<'a> std::fmt::Debug for Armband<'a>
+{
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result
+    {
+        fmt.debug_tuple("Armband").field(&self.raw_id()).finish()
+    }
 }
 
 /// Event Object
 pub trait Event
 {
-    /// Retrieve Event Handle
-    fn handle(&self) -> ffi::libmyo_event_t;
-    
+    /// Retrieve Raw Event Data
+    fn raw(&self) -> &dyn RawEvent;
+
     /// Event Type
-    fn event_type(&self) -> EventType { unsafe { std::mem::transmute(ffi::libmyo_event_get_type(self.handle())) } }
+    fn event_type(&self) -> EventType { self.raw().event_type() }
     /// Timestamp
-    fn timestamp(&self) -> u64 { unsafe { ffi::libmyo_event_get_timestamp(self.handle()) } }
+    fn timestamp(&self) -> u64 { self.raw().timestamp() }
     /// Myo Device
-    fn device(&self) -> Armband { Armband(unsafe { ffi::libmyo_event_get_myo(self.handle()) }) }
+    fn device(&self) -> Armband<'_> { Armband(self.raw().device()) }
     /// MAC Address
-    fn mac_address(&self) -> MACAddress { MACAddress(unsafe { ffi::libmyo_event_get_mac_address(self.handle()) }) }
+    fn mac_address(&self) -> MACAddress { MACAddress(self.raw().mac_address()) }
     /// Myo Name
-    fn device_name(&self) -> MyoString { MyoString(unsafe { ffi::libmyo_event_get_myo_name(self.handle()) }) }
+    fn device_name(&self) -> MyoString { self.raw().device_name() }
+    /// Copy into an owned event
+    fn to_owned_event(&self) -> MyoEvent { MyoEvent::from_raw(self.raw()) }
 }
 macro_rules! DefEvent
 {
-    ($t: ident) =>
+    ($(#[$m: meta])* $t: ident) =>
     {
-        pub struct $t(ffi::libmyo_event_t);
-        impl Event for $t { fn handle(&self) -> ffi::libmyo_event_t { self.0 } }
+        $(#[$m])* #[derive(Clone, Copy)] pub struct $t<'a>(&'a dyn RawEvent);
+        impl<'a> Event for $t<'a> { fn raw(&self) -> &dyn RawEvent { self.0 } }
     }
 }
 macro_rules! DefEventParamWrapper
 {
-    (pub property<$obj: ty> $pn: ident [$($an: ident: $at: ty),*]: $pt: ty = $f: ident ($($fan: expr),*)) =>
+    ($(#[$m: meta])* pub property<$obj: ident> $pn: ident: $pt: ty = $f: ident ($($fan: expr),*)) =>
     {
-        impl $obj
+        impl<'a> $obj<'a>
         {
-            pub fn $pn(&self $(, $an: $at)*) -> $pt
-            {
-                unsafe { ffi::$f(self.0 $(, $fan)*) as _ }
-            }
-        }
-    };
-    (pub property<$obj: ty> $pn: ident: $pt: ty = $f: ident ($($fan: expr),*)) =>
-    {
-        impl $obj
-        {
-            pub fn $pn(&self) -> $pt
-            {
-                unsafe { ffi::$f(self.0 $(, $fan)*) as _ }
-            }
+            $(#[$m])* pub fn $pn(&self) -> $pt { self.0.$f($($fan),*) }
         }
     };
 }
 
-/// Successfully paired with a Myo.
-DefEvent!(PairedEvent);
-/// Successfully unpaired from a Myo.
-DefEvent!(UnpairedEvent);
-/// A Myo has successfully connected.
-DefEvent!(ConnectedEvent);
-/// A Myo has been disconnected.
-DefEvent!(DisconnectedEvent);
-/// A Myo has recognized that the sync gesture has been successfully performed.
-DefEvent!(ArmSyncedEvent);
-/// A Myo has been moved or removed from the arm.
-DefEvent!(ArmUnsyncedEvent);
-/// Orientation data has been received.
-DefEvent!(OrientationEvent);
-/// A change in pose has been detected.
-DefEvent!(PoseEvent);
-/// An RSSI value has been received.
-DefEvent!(RSSIEvent);
-/// A Myo has become unlocked.
-DefEvent!(UnlockedEvent);
-/// A Myo has become locked.
-DefEvent!(LockedEvent);
-/// EMG data has been received.
-DefEvent!(EMGEvent);
-/// A battery level value has been received.
-DefEvent!(BatteryLevelEvent);
-/// The warmup period has completed.
-DefEvent!(WarmupCompletedEvent);
-
-impl PairedEvent
-{
-    /// Firmware Version
-    DefWrapperFunc!(pub fn firmware_version(component: VersionComponent) -> u32 = libmyo_event_get_firmware_version(component));
+DefEvent!(/// Successfully paired with a Myo.
+    PairedEvent);
+DefEvent!(/// Successfully unpaired from a Myo.
+    UnpairedEvent);
+DefEvent!(/// A Myo has successfully connected.
+    ConnectedEvent);
+DefEvent!(/// A Myo has been disconnected.
+    DisconnectedEvent);
+DefEvent!(/// A Myo has recognized that the sync gesture has been successfully performed.
+    ArmSyncedEvent);
+DefEvent!(/// A Myo has been moved or removed from the arm.
+    ArmUnsyncedEvent);
+DefEvent!(/// Orientation data has been received.
+    OrientationEvent);
+DefEvent!(/// A change in pose has been detected.
+    PoseEvent);
+DefEvent!(/// An RSSI value has been received.
+    RSSIEvent);
+DefEvent!(/// A Myo has become unlocked.
+    UnlockedEvent);
+DefEvent!(/// A Myo has become locked.
+    LockedEvent);
+DefEvent!(/// EMG data has been received.
+    EMGEvent);
+DefEvent!(/// A battery level value has been received.
+    BatteryLevelEvent);
+DefEvent!(/// The warmup period has completed.
+    WarmupCompletedEvent);
+
+impl<'a> PairedEvent<'a>
+{
+    DefWrapperFunc!(/// Firmware Version
+        pub fn firmware_version(component: VersionComponent) -> u32 = firmware_version(component));
 
     /// Firmware Version Set
     pub fn firmware_versions(&self) -> (u32, u32, u32, HardwareRevision)
@@ -319,13 +548,13 @@ impl PairedEvent
         (self.firmware_version(VersionComponent::Major),
         self.firmware_version(VersionComponent::Minor),
         self.firmware_version(VersionComponent::Patch),
-        unsafe { std::mem::transmute(self.firmware_version(VersionComponent::HardwareRevision)) })
+        HardwareRevision::from_raw(self.firmware_version(VersionComponent::HardwareRevision)).unwrap_or(HardwareRevision::Unknown))
     }
 }
-impl ConnectedEvent
+impl<'a> ConnectedEvent<'a>
 {
-    /// Firmware Version
-    DefWrapperFunc!(pub fn firmware_version(component: VersionComponent) -> u32 = libmyo_event_get_firmware_version(component));
+    DefWrapperFunc!(/// Firmware Version
+        pub fn firmware_version(component: VersionComponent) -> u32 = firmware_version(component));
 
     /// Firmware Version Set
     pub fn firmware_versions(&self) -> (u32, u32, u32, HardwareRevision)
@@ -333,22 +562,22 @@ impl ConnectedEvent
         (self.firmware_version(VersionComponent::Major),
         self.firmware_version(VersionComponent::Minor),
         self.firmware_version(VersionComponent::Patch),
-        unsafe { std::mem::transmute(self.firmware_version(VersionComponent::HardwareRevision)) })
+        HardwareRevision::from_raw(self.firmware_version(VersionComponent::HardwareRevision)).unwrap_or(HardwareRevision::Unknown))
     }
 }
 
-impl ArmSyncedEvent
+impl<'a> ArmSyncedEvent<'a>
 {
-    /// Arm Side
-    DefWrapperFunc!(pub fn arm() -> Arm = libmyo_event_get_arm());
-    /// +x Direction
-    DefWrapperFunc!(pub fn xdirection() -> XDirection = libmyo_event_get_x_direction());
-    /// Warming up state
-    DefWrapperFunc!(pub fn warmup_state() -> WarmupState = libmyo_event_get_warmup_state());
-    /// Eastimated Rotation of Myo on the user's arm
-    DefWrapperFunc!(pub fn rotation_on_arm() -> f32 = libmyo_event_get_rotation_on_arm());
+    DefWrapperFunc!(/// Arm Side
+        pub fn arm() -> Arm = arm());
+    DefWrapperFunc!(/// +x Direction
+        pub fn xdirection() -> XDirection = x_direction());
+    DefWrapperFunc!(/// Warming up state
+        pub fn warmup_state() -> WarmupState = warmup_state());
+    DefWrapperFunc!(/// Eastimated Rotation of Myo on the user's arm
+        pub fn rotation_on_arm() -> f32 = rotation_on_arm());
 }
-DefEventParamWrapper!(pub property<WarmupCompletedEvent> result: WarmupResult = libmyo_event_get_warmup_result());
+DefEventParamWrapper!(pub property<WarmupCompletedEvent> result: WarmupResult = warmup_result());
 
 /// Accelerometer/Gyroscope Index Value
 #[repr(u8)] pub enum CoordinateIndex
@@ -356,45 +585,44 @@ DefEventParamWrapper!(pub property<WarmupCompletedEvent> result: WarmupResult =
     X = 0, Y = 1, Z = 2
 }
 
-impl OrientationEvent
+impl<'a> OrientationEvent<'a>
 {
-    /// Orientation data
-    DefWrapperFunc!(pub fn orientation(index: OrientationIndex) -> f32 = libmyo_event_get_orientation(index));
-    /// Accelerometer data
-    DefWrapperFunc!(pub fn accelerometer(index: CoordinateIndex) -> f32 = libmyo_event_get_accelerometer(index as _));
-    /// Gyroscope data
-    DefWrapperFunc!(pub fn gyroscope(index: CoordinateIndex) -> f32 = libmyo_event_get_gyroscope(index as _));
+    DefWrapperFunc!(/// Orientation data
+        pub fn orientation(index: OrientationIndex) -> f32 = orientation(index));
+    DefWrapperFunc!(/// Accelerometer data
+        pub fn accelerometer(index: CoordinateIndex) -> f32 = accelerometer(index as _));
+    DefWrapperFunc!(/// Gyroscope data
+        pub fn gyroscope(index: CoordinateIndex) -> f32 = gyroscope(index as _));
 
     // Support Funcs
     /// Orientation Data
-    pub fn q_orientation(&self) -> (f32, f32, f32, f32)
+    pub fn q_orientation(&self) -> Quaternion
     {
-        (self.orientation(OrientationIndex::X), self.orientation(OrientationIndex::Y),
+        Quaternion::new(self.orientation(OrientationIndex::X), self.orientation(OrientationIndex::Y),
             self.orientation(OrientationIndex::Z), self.orientation(OrientationIndex::W))
     }
     /// Accelerometer Data
-    pub fn v_accelerometer(&self) -> (f32, f32, f32)
+    pub fn v_accelerometer(&self) -> Vec3
     {
-        (self.accelerometer(CoordinateIndex::X), self.accelerometer(CoordinateIndex::Y), self.accelerometer(CoordinateIndex::Z))
+        Vec3::new(self.accelerometer(CoordinateIndex::X), self.accelerometer(CoordinateIndex::Y), self.accelerometer(CoordinateIndex::Z))
     }
     /// Gyroscope Data
-    pub fn v_gyroscope(&self) -> (f32, f32, f32)
+    pub fn v_gyroscope(&self) -> Vec3
     {
-        (self.gyroscope(CoordinateIndex::X), self.gyroscope(CoordinateIndex::Y), self.gyroscope(CoordinateIndex::Z))
+        Vec3::new(self.gyroscope(CoordinateIndex::X), self.gyroscope(CoordinateIndex::Y), self.gyroscope(CoordinateIndex::Z))
     }
 }
-DefEventParamWrapper!(pub property<PoseEvent> pose: Pose = libmyo_event_get_pose());
-DefEventParamWrapper!(pub property<RSSIEvent> rssi: i8 = libmyo_event_get_rssi());
-DefEventParamWrapper!(pub property<BatteryLevelEvent> battery_level: u8 = libmyo_event_get_battery_level());
-impl EMGEvent
+DefEventParamWrapper!(pub property<PoseEvent> pose: Pose = pose());
+DefEventParamWrapper!(pub property<RSSIEvent> rssi: i8 = rssi());
+DefEventParamWrapper!(pub property<BatteryLevelEvent> battery_level: u8 = battery_level());
+impl<'a> EMGEvent<'a>
 {
-    /// EMG Data
-    DefWrapperFunc!(pub fn emg(sensor: u8) -> i8 = libmyo_event_get_emg(sensor as _));
+    DefWrapperFunc!(/// EMG Data
+        pub fn emg(sensor: u8) -> i8 = emg(sensor as _));
 
     /// EMGs
     pub fn emgs(&self) -> [i8; 8]
     {
-        let mut a = [0; 8]; for n in 0 .. 8 { a[n] = self.emg(n as _); } a
+        let mut a = [0; 8]; for (n, v) in a.iter_mut().enumerate() { *v = self.emg(n as _); } a
     }
 }
-
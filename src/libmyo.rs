@@ -0,0 +1,148 @@
+//! libmyo Backend
+
+use ffi;
+use {Result, Error, MyoString, ResultCode, HandlerResult, LockingPolicy, VersionComponent, EventType,
+    Arm, XDirection, WarmupState, WarmupResult, Pose, OrientationIndex};
+use error::validate_application_identifier;
+use backend::{Backend, BackendHandler, RawEvent, Device, Command};
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+
+/// Call a libmyo function taking `out_error` as the last argument
+macro_rules! LibmyoCall
+{
+    ($fname: ident ( $($farg: expr),* )) =>
+    {{
+        let mut e = std::ptr::null_mut();
+        let r = unsafe { ffi::$fname($($farg, )* &mut e) };
+        if r == ResultCode::Success { Ok(()) } else { Err(Error::libmyo(e)) }
+    }}
+}
+
+/// Store for Trait Object(stable passing for std::raw::TraitObject), with the payload of a panic in the handler
+struct TraitObjectStore<'a, T: 'a + ?Sized>(&'a mut T, Option<Box<dyn Any + Send>>);
+
+/// Backend using the Myo Connect hub through libmyo
+pub struct LibmyoBackend(ffi::libmyo_hub_t);
+impl LibmyoBackend
+{
+    /// Initialize
+    pub fn init<AppID: Into<Vec<u8>>>(application_identifier: AppID) -> Result<Self>
+    {
+        let appid = String::from_utf8(application_identifier.into()).map_err(|e| Error::InvalidApplicationIdentifier
+        {
+            identifier: String::from_utf8_lossy(e.as_bytes()).into_owned(), source: None
+        })?;
+        validate_application_identifier(&appid)?;
+        ffi::ensure_loaded().map_err(Error::LibraryMissing)?;
+        let mut h = std::ptr::null_mut();
+        let appid_c = std::ffi::CString::new(appid.clone()).unwrap();
+        LibmyoCall!(libmyo_init_hub(&mut h, appid_c.as_ptr())).map(|_| LibmyoBackend(h)).map_err(|e| match e
+        {
+            Error::InvalidArgument(_) => Error::InvalidApplicationIdentifier { identifier: appid, source: Some(Box::new(e)) },
+            e => e
+        })
+    }
+
+    /// Pass an event to the handler of `libmyo_run`.
+    /// Events of unknown types are skipped, and a panic must not unwind into libmyo: it is kept to be resumed by `run`.
+    extern "system" fn run_internal_handler(elptr: *mut libc::c_void, event: ffi::libmyo_event_t) -> HandlerResult
+    {
+        let handler = unsafe { &mut *(elptr as *mut TraitObjectStore<BackendHandler>) };
+        if handler.1.is_some() { return HandlerResult::Stop; }
+        let event_type = match EventType::from_raw(unsafe { ffi::libmyo_event_get_type(event) }) { Some(t) => t, None => return HandlerResult::Continue };
+        let event = LibmyoEvent(event, LibmyoDevice(unsafe { ffi::libmyo_event_get_myo(event) }), event_type);
+        match std::panic::catch_unwind(AssertUnwindSafe(|| (handler.0)(&event)))
+        {
+            Ok(r) => r,
+            Err(p) => { handler.1 = Some(p); HandlerResult::Stop }
+        }
+    }
+}
+// The hub can be moved to another thread, but must not be used from multiple threads at once
+unsafe impl Send for LibmyoBackend {}
+impl Backend for LibmyoBackend
+{
+    fn set_locking_policy(&self, locking_policy: LockingPolicy) -> Result<()>
+    {
+        LibmyoCall!(libmyo_set_locking_policy(self.0, locking_policy))
+    }
+    fn run(&self, duration_ms: u32, handler: &mut BackendHandler) -> Result<()>
+    {
+        let mut to = TraitObjectStore(handler, None);
+        let r = LibmyoCall!(libmyo_run(self.0, duration_ms as _, Self::run_internal_handler, &mut to as *mut TraitObjectStore<_> as *mut libc::c_void));
+        if let Some(p) = to.1 { std::panic::resume_unwind(p); }
+        r
+    }
+    fn shutdown(&mut self) -> Result<()>
+    {
+        // libmyo does not define the state of a hub that failed to shut down: never try again
+        let h = std::mem::replace(&mut self.0, std::ptr::null_mut());
+        if h.is_null() { Ok(()) } else { LibmyoCall!(libmyo_shutdown_hub(h)) }
+    }
+}
+impl Drop for LibmyoBackend
+{
+    /// Shutdown, logging a failure(panicking here could abort the process during unwinding)
+    fn drop(&mut self)
+    {
+        if let Err(e) = self.shutdown() { eprintln!("myo: failed to shut down the hub: {}", e); }
+    }
+}
+
+/// libmyo Device
+#[derive(Clone, Copy)] struct LibmyoDevice(ffi::libmyo_myo_t);
+/// libmyo keeps a `libmyo_myo_t` alive until the device is unpaired, independently of the calling thread
+unsafe impl Send for LibmyoDevice {}
+impl Device for LibmyoDevice
+{
+    fn raw_id(&self) -> usize { self.0 as usize }
+    fn execute(&self, command: Command) -> Result<()>
+    {
+        match command
+        {
+            Command::Vibrate(vtype) => LibmyoCall!(libmyo_vibrate(self.0, vtype)),
+            Command::RequestRSSI => LibmyoCall!(libmyo_request_rssi(self.0)),
+            Command::RequestBatteryLevel => LibmyoCall!(libmyo_request_battery_level(self.0)),
+            Command::SetStreamEMG(stream_emg_data) => LibmyoCall!(libmyo_set_stream_emg(self.0,
+                if stream_emg_data { ffi::EMGStreamingMode::Enabled } else { ffi::EMGStreamingMode::Disabled })),
+            Command::Lock => LibmyoCall!(libmyo_myo_lock(self.0)),
+            Command::Unlock(unlock_type) => LibmyoCall!(libmyo_myo_unlock(self.0, unlock_type)),
+            Command::NotifyUserAction => LibmyoCall!(libmyo_myo_notify_user_action(self.0, ffi::UserActionType::Single))
+        }
+    }
+    fn retain(&self) -> Box<dyn Device + Send> { Box::new(*self) }
+}
+
+/// libmyo Event(valid only inside the `libmyo_run` callback) of a known type
+struct LibmyoEvent(ffi::libmyo_event_t, LibmyoDevice, EventType);
+impl RawEvent for LibmyoEvent
+{
+    fn event_type(&self) -> EventType { self.2 }
+    fn timestamp(&self) -> u64 { unsafe { ffi::libmyo_event_get_timestamp(self.0) } }
+    fn device(&self) -> &dyn Device { &self.1 }
+    fn mac_address(&self) -> u64 { unsafe { ffi::libmyo_event_get_mac_address(self.0) } }
+    fn device_name(&self) -> MyoString { MyoString::libmyo(unsafe { ffi::libmyo_event_get_myo_name(self.0) }) }
+    fn firmware_version(&self, component: VersionComponent) -> u32 { unsafe { ffi::libmyo_event_get_firmware_version(self.0, component) as _ } }
+    fn arm(&self) -> Arm { Arm::from_raw(unsafe { ffi::libmyo_event_get_arm(self.0) } as _).unwrap_or(Arm::Unknown) }
+    fn x_direction(&self) -> XDirection
+    {
+        XDirection::from_raw(unsafe { ffi::libmyo_event_get_x_direction(self.0) } as _).unwrap_or(XDirection::Unknown)
+    }
+    fn warmup_state(&self) -> WarmupState
+    {
+        WarmupState::from_raw(unsafe { ffi::libmyo_event_get_warmup_state(self.0) } as _).unwrap_or(WarmupState::Unknown)
+    }
+    fn warmup_result(&self) -> WarmupResult
+    {
+        WarmupResult::from_raw(unsafe { ffi::libmyo_event_get_warmup_result(self.0) } as _).unwrap_or(WarmupResult::Unknown)
+    }
+    fn rotation_on_arm(&self) -> f32 { unsafe { ffi::libmyo_event_get_rotation_on_arm(self.0) } }
+    fn orientation(&self, index: OrientationIndex) -> f32 { unsafe { ffi::libmyo_event_get_orientation(self.0, index) } }
+    fn accelerometer(&self, index: u32) -> f32 { unsafe { ffi::libmyo_event_get_accelerometer(self.0, index as _) } }
+    fn gyroscope(&self, index: u32) -> f32 { unsafe { ffi::libmyo_event_get_gyroscope(self.0, index as _) } }
+    fn pose(&self) -> Pose { Pose::from_raw(unsafe { ffi::libmyo_event_get_pose(self.0) } as _).unwrap_or(Pose::unknown) }
+    fn rssi(&self) -> i8 { unsafe { ffi::libmyo_event_get_rssi(self.0) } }
+    fn battery_level(&self) -> u8 { unsafe { ffi::libmyo_event_get_battery_level(self.0) } }
+    fn emg(&self, sensor: u32) -> i8 { unsafe { ffi::libmyo_event_get_emg(self.0, sensor as _) } }
+}
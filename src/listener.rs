@@ -0,0 +1,238 @@
+//! Closure-based Event Listener and Listener Combinators
+
+use {EventListener, HandlerResult, Event, EventType, MACAddress, MyoEvent, RawEvent, dispatch};
+use owned::OwnedRawEvent;
+use std::io;
+use {PairedEvent, UnpairedEvent, ConnectedEvent, DisconnectedEvent, ArmSyncedEvent, ArmUnsyncedEvent, OrientationEvent,
+    PoseEvent, RSSIEvent, UnlockedEvent, LockedEvent, EMGEvent, BatteryLevelEvent, WarmupCompletedEvent};
+
+/// Values that closures of `Listener` can return
+pub trait IntoHandlerResult
+{
+    fn into_handler_result(self) -> HandlerResult;
+}
+impl IntoHandlerResult for HandlerResult
+{
+    fn into_handler_result(self) -> HandlerResult { self }
+}
+/// `()` continues processing events
+impl IntoHandlerResult for ()
+{
+    fn into_handler_result(self) -> HandlerResult { HandlerResult::Continue }
+}
+
+macro_rules! DefListener
+{
+    ($($(#[$m: meta])* $builder: ident => $handler: ident($ev: ident)),*) =>
+    {
+        /// Event Listener built from closures:
+        /// `Listener::new().on_pose(|e| println!("{:?}", e.pose())).on_emg(|e| ...)`.
+        ///
+        /// Closures registered for the same event are called in order of registration.
+        /// The run stops when any of them returns `HandlerResult::Stop`.
+        /// Events without closures continue processing.
+        pub struct Listener<'l>
+        {
+            $($handler: Vec<Box<dyn FnMut($ev) -> HandlerResult + 'l>>),*
+        }
+        impl<'l> Listener<'l>
+        {
+            /// Listener without any closures
+            pub fn new() -> Self { Listener { $($handler: Vec::new()),* } }
+            $(
+                $(#[$m])*
+                pub fn $builder<F, R>(mut self, mut handler: F) -> Self where F: FnMut($ev) -> R + 'l, R: IntoHandlerResult
+                {
+                    self.$handler.push(Box::new(move |e| handler(e).into_handler_result()));
+                    self
+                }
+            )*
+        }
+        impl<'l> EventListener for Listener<'l>
+        {
+            $(
+                fn $handler(&mut self, event: $ev) -> HandlerResult
+                {
+                    self.$handler.iter_mut().fold(HandlerResult::Continue, |r, h| r.combine(h(event)))
+                }
+            )*
+        }
+    }
+}
+DefListener!
+{
+    /// Called when successfully paired with a Myo.
+    on_paired => on_paired(PairedEvent),
+    /// Called when successfully unpaired from a Myo.
+    on_unpaired => on_unpaired(UnpairedEvent),
+    /// Called when a Myo has successfully connected.
+    on_connected => on_connected(ConnectedEvent),
+    /// Called when a Myo has been disconnected.
+    on_disconnected => on_disconnected(DisconnectedEvent),
+    /// Called when a Myo has recognized that the sync gesture has been successfully performed.
+    on_arm_synced => on_arm_synced(ArmSyncedEvent),
+    /// Called when a Myo has been moved or removed from the arm.
+    on_arm_unsynced => on_arm_unsynced(ArmUnsyncedEvent),
+    /// Called when orientation data has been received.
+    on_orientation => on_orientation_data(OrientationEvent),
+    /// Called when a change in pose has been detected.
+    on_pose => on_pose(PoseEvent),
+    /// Called when an RSSI value has been received.
+    on_rssi => on_rssi_value(RSSIEvent),
+    /// Called when a Myo has become unlocked.
+    on_unlocked => on_unlocked(UnlockedEvent),
+    /// Called when a Myo has become locked.
+    on_locked => on_locked(LockedEvent),
+    /// Called when EMG data has been received.
+    on_emg => on_emg_data(EMGEvent),
+    /// Called when a battery level value has been received.
+    on_battery_level => on_battery_level(BatteryLevelEvent),
+    /// Called when the warmup period has completed.
+    on_warmup_completed => on_warmup_completed(WarmupCompletedEvent)
+}
+impl<'l> Default for Listener<'l>
+{
+    fn default() -> Self { Self::new() }
+}
+
+/// Event Listener that handles every event in a single method
+trait RawListener
+{
+    fn dispatch_raw(&mut self, event: &dyn RawEvent) -> HandlerResult;
+}
+macro_rules! DefRawListener
+{
+    ([$($g: tt)*] $t: ty; $($handler: ident($ev: ident) = $_t: ident),*) =>
+    {
+        impl<$($g)*> EventListener for $t
+        {
+            $(fn $handler(&mut self, event: $ev) -> HandlerResult { self.dispatch_raw(event.raw()) })*
+        }
+    }
+}
+macro_rules! DefForwardingListener
+{
+    ([$($g: tt)*] $t: ty; $($handler: ident($ev: ident) = $_t: ident),*) =>
+    {
+        /// Forwards every event to the referenced listener
+        impl<$($g)*> EventListener for $t
+        {
+            $(fn $handler(&mut self, event: $ev) -> HandlerResult { (**self).$handler(event) })*
+        }
+    }
+}
+ForEachEventHandler!(DefForwardingListener ['a, L: EventListener + ?Sized + 'a] &'a mut L;);
+ForEachEventHandler!(DefForwardingListener [L: EventListener + ?Sized] Box<L>;);
+
+macro_rules! DefTupleListener
+{
+    ($(($($l: ident . $n: tt),+)),*) =>
+    {
+        $(
+            impl<$($l: EventListener),+> RawListener for ($($l,)+)
+            {
+                fn dispatch_raw(&mut self, event: &dyn RawEvent) -> HandlerResult
+                {
+                    HandlerResult::Continue $(.combine(dispatch(&mut self.$n, event)))+
+                }
+            }
+            ForEachEventHandler!(DefRawListener [$($l: EventListener),+] ($($l,)+););
+        )*
+    }
+}
+// Tuples of listeners fan out like `Vec<L>`
+DefTupleListener!
+{
+    (A.0, B.1), (A.0, B.1, C.2), (A.0, B.1, C.2, D.3), (A.0, B.1, C.2, D.3, E.4), (A.0, B.1, C.2, D.3, E.4, F.5),
+    (A.0, B.1, C.2, D.3, E.4, F.5, G.6), (A.0, B.1, C.2, D.3, E.4, F.5, G.6, H.7)
+}
+/// Fan-out: every listener receives every event in order, and the run stops afterwards if any of them returned `Stop`
+impl<L: EventListener> RawListener for Vec<L>
+{
+    fn dispatch_raw(&mut self, event: &dyn RawEvent) -> HandlerResult
+    {
+        self.iter_mut().fold(HandlerResult::Continue, |r, l| r.combine(dispatch(l, event)))
+    }
+}
+ForEachEventHandler!(DefRawListener [L: EventListener] Vec<L>;);
+
+/// Predicate of `Filter`
+pub type EventPredicate<'f> = dyn FnMut(&dyn RawEvent) -> bool + 'f;
+
+/// Passes only the events accepted by a predicate to the inner listener.
+/// Rejected events continue processing.
+pub struct Filter<'f, L: EventListener>
+{
+    listener: L, predicate: Box<EventPredicate<'f>>
+}
+impl<'f, L: EventListener> Filter<'f, L>
+{
+    /// Filter with an arbitrary predicate
+    pub fn new<P: FnMut(&dyn RawEvent) -> bool + 'f>(listener: L, predicate: P) -> Self
+    {
+        Filter { listener, predicate: Box::new(predicate) }
+    }
+    /// Pass only the events of `types`
+    pub fn event_types(listener: L, types: &[EventType]) -> Self
+    {
+        let types = types.to_vec();
+        Self::new(listener, move |e| types.contains(&e.event_type()))
+    }
+    /// Pass only the events from the armbands of `mac_addresses`
+    pub fn devices(listener: L, mac_addresses: &[MACAddress]) -> Self
+    {
+        let macs = mac_addresses.to_vec();
+        Self::new(listener, move |e| macs.contains(&MACAddress(e.mac_address())))
+    }
+    /// Inner Listener
+    pub fn listener(&mut self) -> &mut L { &mut self.listener }
+    /// Take back the inner listener
+    pub fn into_inner(self) -> L { self.listener }
+}
+impl<'f, L: EventListener> RawListener for Filter<'f, L>
+{
+    fn dispatch_raw(&mut self, event: &dyn RawEvent) -> HandlerResult
+    {
+        if (self.predicate)(event) { dispatch(&mut self.listener, event) } else { HandlerResult::Continue }
+    }
+}
+ForEachEventHandler!(DefRawListener ['f, L: EventListener] Filter<'f, L>;);
+
+/// Transforms events before passing them to the inner listener.
+/// The transform receives an owned copy of each event and may change its type or drop it by returning `None`,
+/// in which case processing continues. Transformed events keep the device of the original event.
+pub struct Map<'f, L: EventListener>
+{
+    listener: L, transform: Box<dyn FnMut(MyoEvent) -> Option<MyoEvent> + 'f>
+}
+impl<'f, L: EventListener> Map<'f, L>
+{
+    /// Map with a transform
+    pub fn new<T: FnMut(MyoEvent) -> Option<MyoEvent> + 'f>(listener: L, transform: T) -> Self
+    {
+        Map { listener, transform: Box::new(transform) }
+    }
+    /// Inner Listener
+    pub fn listener(&mut self) -> &mut L { &mut self.listener }
+    /// Take back the inner listener
+    pub fn into_inner(self) -> L { self.listener }
+}
+impl<'f, L: EventListener> RawListener for Map<'f, L>
+{
+    fn dispatch_raw(&mut self, event: &dyn RawEvent) -> HandlerResult
+    {
+        match (self.transform)(MyoEvent::from_raw(event))
+        {
+            Some(e) => dispatch(&mut self.listener, &OwnedRawEvent(e, event.device())),
+            None => HandlerResult::Continue
+        }
+    }
+}
+ForEachEventHandler!(DefRawListener ['f, L: EventListener] Map<'f, L>;);
+
+/// Event Listener writing to an output, attachable to a hub(`Hub::attach`) which flushes it at `Hub::shutdown`
+pub trait EventSink: EventListener
+{
+    /// Write buffered events, or report the error that stopped the sink
+    fn flush(&mut self) -> io::Result<()>;
+}
@@ -0,0 +1,208 @@
+//! Quaternion and 3D Vector Math for Orientation Data
+
+use std::ops::{Add, Sub, Mul, Neg};
+
+/// 3D Vector
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Vec3 { pub x: f32, pub y: f32, pub z: f32 }
+impl Vec3
+{
+    pub fn new(x: f32, y: f32, z: f32) -> Self { Vec3 { x, y, z } }
+    /// Dot product
+    pub fn dot(self, other: Vec3) -> f32 { self.x * other.x + self.y * other.y + self.z * other.z }
+    /// Cross product
+    pub fn cross(self, other: Vec3) -> Vec3
+    {
+        Vec3::new(self.y * other.z - self.z * other.y, self.z * other.x - self.x * other.z, self.x * other.y - self.y * other.x)
+    }
+    /// Euclidean length
+    pub fn length(self) -> f32 { self.dot(self).sqrt() }
+    /// Vector of length 1 in the same direction, or the zero vector unchanged
+    pub fn normalize(self) -> Vec3
+    {
+        let l = self.length();
+        if l > 0.0 { self * (1.0 / l) } else { self }
+    }
+}
+impl Add for Vec3 { type Output = Vec3; fn add(self, o: Vec3) -> Vec3 { Vec3::new(self.x + o.x, self.y + o.y, self.z + o.z) } }
+impl Sub for Vec3 { type Output = Vec3; fn sub(self, o: Vec3) -> Vec3 { Vec3::new(self.x - o.x, self.y - o.y, self.z - o.z) } }
+impl Mul<f32> for Vec3 { type Output = Vec3; fn mul(self, s: f32) -> Vec3 { Vec3::new(self.x * s, self.y * s, self.z * s) } }
+impl Neg for Vec3 { type Output = Vec3; fn neg(self) -> Vec3 { Vec3::new(-self.x, -self.y, -self.z) } }
+impl From<(f32, f32, f32)> for Vec3 { fn from(v: (f32, f32, f32)) -> Self { Vec3::new(v.0, v.1, v.2) } }
+impl From<[f32; 3]> for Vec3 { fn from(v: [f32; 3]) -> Self { Vec3::new(v[0], v[1], v[2]) } }
+impl From<Vec3> for (f32, f32, f32) { fn from(v: Vec3) -> Self { (v.x, v.y, v.z) } }
+impl From<Vec3> for [f32; 3] { fn from(v: Vec3) -> Self { [v.x, v.y, v.z] } }
+
+/// Quaternion `w + x * i + y * j + z * k`, in the component order of libmyo.
+/// Orientations are unit quaternions rotating vectors of the armband frame into the world frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Quaternion { pub x: f32, pub y: f32, pub z: f32, pub w: f32 }
+impl Default for Quaternion
+{
+    fn default() -> Self { Quaternion::identity() }
+}
+impl Quaternion
+{
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self { Quaternion { x, y, z, w } }
+    /// No rotation
+    pub fn identity() -> Self { Quaternion::new(0.0, 0.0, 0.0, 1.0) }
+    /// Rotation by `angle` radians around `axis`(normalized here)
+    pub fn from_axis_angle(axis: Vec3, angle: f32) -> Self
+    {
+        let (s, c) = (angle * 0.5).sin_cos();
+        let a = axis.normalize() * s;
+        Quaternion::new(a.x, a.y, a.z, c)
+    }
+    /// Rotation by Euler angles in radians: yaw around z, then pitch around y, then roll around x(the inverse of `euler_angles`)
+    pub fn from_euler_angles(roll: f32, pitch: f32, yaw: f32) -> Self
+    {
+        Quaternion::from_axis_angle(Vec3::new(0.0, 0.0, 1.0), yaw) * Quaternion::from_axis_angle(Vec3::new(0.0, 1.0, 0.0), pitch) *
+            Quaternion::from_axis_angle(Vec3::new(1.0, 0.0, 0.0), roll)
+    }
+
+    /// Vector part
+    pub fn vector(self) -> Vec3 { Vec3::new(self.x, self.y, self.z) }
+    /// Dot product of the components
+    pub fn dot(self, other: Quaternion) -> f32 { self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w }
+    /// Norm
+    pub fn norm(self) -> f32 { self.dot(self).sqrt() }
+    /// Unit quaternion of the same rotation, or the identity for a zero quaternion
+    pub fn normalize(self) -> Self
+    {
+        let n = self.norm();
+        if n > 0.0 { self.scale(1.0 / n) } else { Quaternion::identity() }
+    }
+    /// Conjugate, the inverse of a unit quaternion
+    pub fn conjugate(self) -> Self { Quaternion::new(-self.x, -self.y, -self.z, self.w) }
+    /// Inverse, or `None` for a zero quaternion
+    pub fn inverse(self) -> Option<Self>
+    {
+        let n2 = self.dot(self);
+        if n2 > 0.0 { Some(self.conjugate().scale(1.0 / n2)) } else { None }
+    }
+    /// Rotate `v` by this unit quaternion
+    pub fn rotate(self, v: Vec3) -> Vec3
+    {
+        // v + 2w(u x v) + 2u x (u x v)
+        let u = self.vector();
+        let t = u.cross(v) * 2.0;
+        v + t * self.w + u.cross(t)
+    }
+    /// Spherical linear interpolation between unit quaternions along the shorter arc: `t = 0` is `self`, `t = 1` is `other`
+    pub fn slerp(self, other: Quaternion, t: f32) -> Self
+    {
+        let mut d = self.dot(other);
+        let other = if d < 0.0 { d = -d; other.scale(-1.0) } else { other };
+        // nearly parallel: linear interpolation avoids dividing by sin(0)
+        if d > 0.9995 { return (self.scale(1.0 - t) + other.scale(t)).normalize(); }
+        let theta = d.acos();
+        let s = theta.sin();
+        self.scale(((1.0 - t) * theta).sin() / s) + other.scale((t * theta).sin() / s)
+    }
+    /// Rotation matrix of this unit quaternion in row-major order, `m * v == self.rotate(v)`
+    pub fn rotation_matrix(self) -> [[f32; 3]; 3]
+    {
+        let Quaternion { x, y, z, w } = self;
+        [
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+            [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+            [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)]
+        ]
+    }
+    /// Rotation around x in radians(-pi to pi)
+    pub fn roll(self) -> f32
+    {
+        (2.0 * (self.w * self.x + self.y * self.z)).atan2(1.0 - 2.0 * (self.x * self.x + self.y * self.y))
+    }
+    /// Rotation around y in radians(-pi/2 to pi/2)
+    pub fn pitch(self) -> f32 { (2.0 * (self.w * self.y - self.z * self.x)).clamp(-1.0, 1.0).asin() }
+    /// Rotation around z in radians(-pi to pi)
+    pub fn yaw(self) -> f32
+    {
+        (2.0 * (self.w * self.z + self.x * self.y)).atan2(1.0 - 2.0 * (self.y * self.y + self.z * self.z))
+    }
+    /// `(roll, pitch, yaw)` in radians
+    pub fn euler_angles(self) -> (f32, f32, f32) { (self.roll(), self.pitch(), self.yaw()) }
+
+    pub(crate) fn scale(self, s: f32) -> Self { Quaternion::new(self.x * s, self.y * s, self.z * s, self.w * s) }
+}
+impl Add for Quaternion
+{
+    type Output = Quaternion;
+    fn add(self, o: Quaternion) -> Quaternion { Quaternion::new(self.x + o.x, self.y + o.y, self.z + o.z, self.w + o.w) }
+}
+/// Hamilton product: `(a * b).rotate(v) == a.rotate(b.rotate(v))`
+impl Mul for Quaternion
+{
+    type Output = Quaternion;
+    fn mul(self, o: Quaternion) -> Quaternion
+    {
+        Quaternion::new(
+            self.w * o.x + self.x * o.w + self.y * o.z - self.z * o.y,
+            self.w * o.y - self.x * o.z + self.y * o.w + self.z * o.x,
+            self.w * o.z + self.x * o.y - self.y * o.x + self.z * o.w,
+            self.w * o.w - self.x * o.x - self.y * o.y - self.z * o.z)
+    }
+}
+/// Rotation of a vector(`rotate`)
+impl Mul<Vec3> for Quaternion
+{
+    type Output = Vec3;
+    fn mul(self, v: Vec3) -> Vec3 { self.rotate(v) }
+}
+impl From<(f32, f32, f32, f32)> for Quaternion { fn from(q: (f32, f32, f32, f32)) -> Self { Quaternion::new(q.0, q.1, q.2, q.3) } }
+impl From<[f32; 4]> for Quaternion { fn from(q: [f32; 4]) -> Self { Quaternion::new(q[0], q[1], q[2], q[3]) } }
+impl From<Quaternion> for (f32, f32, f32, f32) { fn from(q: Quaternion) -> Self { (q.x, q.y, q.z, q.w) } }
+impl From<Quaternion> for [f32; 4] { fn from(q: Quaternion) -> Self { [q.x, q.y, q.z, q.w] } }
+
+#[cfg(test)]
+pub(crate) mod tests
+{
+    use super::{Quaternion, Vec3};
+    use std::f32::consts::{PI, FRAC_PI_2};
+
+    pub(crate) fn assert_close(a: f32, b: f32) { assert!((a - b).abs() < 1e-5, "{} != {}", a, b); }
+    pub(crate) fn assert_vec_close(a: Vec3, b: Vec3) { assert_close(a.x, b.x); assert_close(a.y, b.y); assert_close(a.z, b.z); }
+    pub(crate) fn assert_rotation_close(a: Quaternion, b: Quaternion) { assert_close(a.dot(b).abs(), 1.0); }
+
+    #[test]
+    fn quaternions_rotate_interpolate_and_convert()
+    {
+        let (x, y, z) = (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+        let yaw90 = Quaternion::from_axis_angle(z, FRAC_PI_2);
+        assert_vec_close(yaw90.rotate(x), y);
+        assert_vec_close(yaw90 * y, -x);
+        assert_vec_close(yaw90.conjugate().rotate(y), x);
+        let m = yaw90.rotation_matrix();
+        assert_vec_close(Vec3::new(m[0][0], m[1][0], m[2][0]), y);
+
+        // composition applies the right operand first
+        let roll90 = Quaternion::from_axis_angle(x, FRAC_PI_2);
+        assert_vec_close((yaw90 * roll90).rotate(y), yaw90.rotate(roll90.rotate(y)));
+        assert_rotation_close(yaw90 * yaw90.inverse().unwrap(), Quaternion::identity());
+        assert!(Quaternion::new(0.0, 0.0, 0.0, 0.0).inverse().is_none());
+
+        let q = Quaternion::new(1.0, 2.0, 3.0, 4.0);
+        assert_close(q.normalize().norm(), 1.0);
+        assert_close((q * q.inverse().unwrap()).w, 1.0);
+
+        let (roll, pitch, yaw) = (0.3, -0.4, 2.5);
+        let q = Quaternion::from_euler_angles(roll, pitch, yaw);
+        let (r, p, w) = q.euler_angles();
+        assert_close(r, roll); assert_close(p, pitch); assert_close(w, yaw);
+        assert_close(Quaternion::from_axis_angle(y, 1.2).pitch(), 1.2);
+
+        let half = Quaternion::identity().slerp(Quaternion::from_axis_angle(z, PI * 0.75), 0.5);
+        assert_rotation_close(half, Quaternion::from_axis_angle(z, PI * 0.375));
+        assert_rotation_close(yaw90.slerp(yaw90, 0.3), yaw90);
+        // the shorter arc is taken for opposite signs of the same rotation
+        let neg = Quaternion::new(-yaw90.x, -yaw90.y, -yaw90.z, -yaw90.w);
+        assert_rotation_close(Quaternion::identity().slerp(neg, 0.5), Quaternion::from_axis_angle(z, PI / 4.0));
+
+        assert_vec_close(x.cross(y), z);
+        assert_close(Vec3::new(3.0, 0.0, 4.0).normalize().length(), 1.0);
+        assert_eq!(<[f32; 4]>::from(Quaternion::from((1.0, 2.0, 3.0, 4.0))), [1.0, 2.0, 3.0, 4.0]);
+    }
+}
@@ -0,0 +1,49 @@
+//! Stable Names of Enum Values in text formats
+
+use {EventType, Arm, XDirection, WarmupState, WarmupResult, Pose, HardwareRevision, LockingPolicy, VibrationType, UnlockType};
+
+/// Enum with a stable lowercase name for each value
+pub(crate) trait EnumName: Sized
+{
+    /// All names, in the order of the values
+    const NAMES: &'static [&'static str];
+    fn name(self) -> &'static str;
+    fn from_name(name: &str) -> Option<Self>;
+}
+macro_rules! DefEnumNames
+{
+    ($($t: ident { $($v: ident = $n: expr),* })*) =>
+    {
+        $(
+            impl EnumName for $t
+            {
+                const NAMES: &'static [&'static str] = &[$($n),*];
+                fn name(self) -> &'static str { match self { $($t::$v => $n),* } }
+                fn from_name(name: &str) -> Option<Self> { match name { $($n => Some($t::$v),)* _ => None } }
+            }
+        )*
+    }
+}
+DefEnumNames!
+{
+    EventType
+    {
+        Paired = "paired", Unpaired = "unpaired", Connected = "connected", Disconnected = "disconnected",
+        ArmSynced = "arm_synced", ArmUnsynced = "arm_unsynced", Orientation = "orientation", Pose = "pose", RSSI = "rssi",
+        Unlocked = "unlocked", Locked = "locked", EMG = "emg", BatteryLevel = "battery_level", WarmupCompleted = "warmup_completed"
+    }
+    Arm { Right = "right", Left = "left", Unknown = "unknown" }
+    XDirection { TowardWrist = "toward_wrist", TowardElbow = "toward_elbow", Unknown = "unknown" }
+    WarmupState { Unknown = "unknown", Cold = "cold", Warm = "warm" }
+    WarmupResult { Unknown = "unknown", Success = "success", Timeout = "timeout" }
+    Pose
+    {
+        rest = "rest", fist = "fist", wave_in = "wave_in", wave_out = "wave_out", fingers_spread = "fingers_spread",
+        double_tap = "double_tap", num_poses = "num_poses", unknown = "unknown"
+    }
+    HardwareRevision { Unknown = "unknown", RevC = "rev_c", RevD = "rev_d" }
+    LockingPolicy { None = "none", Standard = "standard" }
+    VibrationType { Short = "short", Medium = "medium", Long = "long" }
+    UnlockType { Timed = "timed", Hold = "hold" }
+}
+
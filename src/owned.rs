@@ -0,0 +1,237 @@
+//! Owned Event Values
+
+use {MACAddress, EventType, VersionComponent, HardwareRevision, Arm, XDirection, WarmupState, WarmupResult, Pose, OrientationIndex};
+use {MyoString, Quaternion, Vec3};
+use backend::{RawEvent, Device};
+
+/// Information common to all events
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EventInfo
+{
+    /// Timestamp
+    pub timestamp: u64,
+    /// MAC Address
+    pub mac_address: MACAddress,
+    /// Myo Name
+    pub device_name: String
+}
+
+/// Firmware Version Set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FirmwareVersion
+{
+    pub major: u32, pub minor: u32, pub patch: u32,
+    pub hardware_revision: HardwareRevision
+}
+
+/// Sync information of `ArmSyncedEvent`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ArmSync
+{
+    /// Arm Side
+    pub arm: Arm,
+    /// +x Direction
+    pub x_direction: XDirection,
+    /// Warming up state
+    pub warmup_state: WarmupState,
+    /// Estimated Rotation of Myo on the user's arm
+    pub rotation_on_arm: f32
+}
+
+/// Sensor data of `OrientationEvent`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OrientationData
+{
+    /// Orientation quaternion
+    pub orientation: Quaternion,
+    /// Accelerometer data in units of g
+    pub accelerometer: Vec3,
+    /// Gyroscope data in units of deg/s
+    pub gyroscope: Vec3
+}
+
+/// Owned Event, independent of the callback lifetime.
+/// Serialized(`serde` feature) as one flat object with `type`, `timestamp`, `mac`, `name` and the fields of its type,
+/// the same as the lines written by `JsonlWriter`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "::serialization::FlatEvent", try_from = "::serialization::FlatEvent"))]
+pub enum MyoEvent
+{
+    /// Successfully paired with a Myo.
+    Paired(EventInfo, FirmwareVersion),
+    /// Successfully unpaired from a Myo.
+    Unpaired(EventInfo),
+    /// A Myo has successfully connected.
+    Connected(EventInfo, FirmwareVersion),
+    /// A Myo has been disconnected.
+    Disconnected(EventInfo),
+    /// A Myo has recognized that the sync gesture has been successfully performed.
+    ArmSynced(EventInfo, ArmSync),
+    /// A Myo has been moved or removed from the arm.
+    ArmUnsynced(EventInfo),
+    /// Orientation data has been received.
+    Orientation(EventInfo, OrientationData),
+    /// A change in pose has been detected.
+    Pose(EventInfo, Pose),
+    /// An RSSI value has been received.
+    RSSI(EventInfo, i8),
+    /// A Myo has become unlocked.
+    Unlocked(EventInfo),
+    /// A Myo has become locked.
+    Locked(EventInfo),
+    /// EMG data has been received.
+    EMG(EventInfo, [i8; 8]),
+    /// A battery level value has been received.
+    BatteryLevel(EventInfo, u8),
+    /// The warmup period has completed.
+    WarmupCompleted(EventInfo, WarmupResult)
+}
+impl MyoEvent
+{
+    /// Copy all data of a raw event
+    pub fn from_raw(raw: &dyn RawEvent) -> Self
+    {
+        let info = EventInfo
+        {
+            timestamp: raw.timestamp(), mac_address: MACAddress(raw.mac_address()),
+            device_name: raw.device_name().c_str().to_string_lossy().into_owned()
+        };
+        let firmware = |raw: &dyn RawEvent| FirmwareVersion
+        {
+            major: raw.firmware_version(VersionComponent::Major),
+            minor: raw.firmware_version(VersionComponent::Minor),
+            patch: raw.firmware_version(VersionComponent::Patch),
+            hardware_revision: HardwareRevision::from_raw(raw.firmware_version(VersionComponent::HardwareRevision)).unwrap_or(HardwareRevision::Unknown)
+        };
+        match raw.event_type()
+        {
+            EventType::Paired => MyoEvent::Paired(info, firmware(raw)),
+            EventType::Unpaired => MyoEvent::Unpaired(info),
+            EventType::Connected => MyoEvent::Connected(info, firmware(raw)),
+            EventType::Disconnected => MyoEvent::Disconnected(info),
+            EventType::ArmSynced => MyoEvent::ArmSynced(info, ArmSync
+            {
+                arm: raw.arm(), x_direction: raw.x_direction(), warmup_state: raw.warmup_state(), rotation_on_arm: raw.rotation_on_arm()
+            }),
+            EventType::ArmUnsynced => MyoEvent::ArmUnsynced(info),
+            EventType::Orientation => MyoEvent::Orientation(info, OrientationData
+            {
+                orientation: Quaternion::new(raw.orientation(OrientationIndex::X), raw.orientation(OrientationIndex::Y),
+                    raw.orientation(OrientationIndex::Z), raw.orientation(OrientationIndex::W)),
+                accelerometer: Vec3::new(raw.accelerometer(0), raw.accelerometer(1), raw.accelerometer(2)),
+                gyroscope: Vec3::new(raw.gyroscope(0), raw.gyroscope(1), raw.gyroscope(2))
+            }),
+            EventType::Pose => MyoEvent::Pose(info, raw.pose()),
+            EventType::RSSI => MyoEvent::RSSI(info, raw.rssi()),
+            EventType::Unlocked => MyoEvent::Unlocked(info),
+            EventType::Locked => MyoEvent::Locked(info),
+            EventType::EMG =>
+            {
+                let mut a = [0; 8]; for (n, v) in a.iter_mut().enumerate() { *v = raw.emg(n as _); }
+                MyoEvent::EMG(info, a)
+            },
+            EventType::BatteryLevel => MyoEvent::BatteryLevel(info, raw.battery_level()),
+            EventType::WarmupCompleted => MyoEvent::WarmupCompleted(info, raw.warmup_result())
+        }
+    }
+
+    /// Common Information
+    pub fn info(&self) -> &EventInfo
+    {
+        match *self
+        {
+            MyoEvent::Paired(ref i, _) | MyoEvent::Connected(ref i, _) | MyoEvent::ArmSynced(ref i, _) |
+            MyoEvent::Orientation(ref i, _) | MyoEvent::Pose(ref i, _) | MyoEvent::RSSI(ref i, _) |
+            MyoEvent::EMG(ref i, _) | MyoEvent::BatteryLevel(ref i, _) | MyoEvent::WarmupCompleted(ref i, _) |
+            MyoEvent::Unpaired(ref i) | MyoEvent::Disconnected(ref i) | MyoEvent::ArmUnsynced(ref i) |
+            MyoEvent::Unlocked(ref i) | MyoEvent::Locked(ref i) => i
+        }
+    }
+    /// Event Type
+    pub fn event_type(&self) -> EventType
+    {
+        match *self
+        {
+            MyoEvent::Paired(..) => EventType::Paired,
+            MyoEvent::Unpaired(..) => EventType::Unpaired,
+            MyoEvent::Connected(..) => EventType::Connected,
+            MyoEvent::Disconnected(..) => EventType::Disconnected,
+            MyoEvent::ArmSynced(..) => EventType::ArmSynced,
+            MyoEvent::ArmUnsynced(..) => EventType::ArmUnsynced,
+            MyoEvent::Orientation(..) => EventType::Orientation,
+            MyoEvent::Pose(..) => EventType::Pose,
+            MyoEvent::RSSI(..) => EventType::RSSI,
+            MyoEvent::Unlocked(..) => EventType::Unlocked,
+            MyoEvent::Locked(..) => EventType::Locked,
+            MyoEvent::EMG(..) => EventType::EMG,
+            MyoEvent::BatteryLevel(..) => EventType::BatteryLevel,
+            MyoEvent::WarmupCompleted(..) => EventType::WarmupCompleted
+        }
+    }
+    /// Timestamp
+    pub fn timestamp(&self) -> u64 { self.info().timestamp }
+    /// MAC Address
+    pub fn mac_address(&self) -> MACAddress { self.info().mac_address }
+    /// Myo Name
+    pub fn device_name(&self) -> &str { &self.info().device_name }
+}
+
+/// Owned event presented as a `RawEvent` of `device`, so that it can be dispatched to event listeners.
+/// Accessors for fields that do not belong to the event's type return zero or the `unknown` value.
+pub struct OwnedRawEvent<'d>(pub MyoEvent, pub &'d dyn Device);
+impl<'d> RawEvent for OwnedRawEvent<'d>
+{
+    fn event_type(&self) -> EventType { self.0.event_type() }
+    fn timestamp(&self) -> u64 { self.0.timestamp() }
+    fn device(&self) -> &dyn Device { self.1 }
+    fn mac_address(&self) -> u64 { self.0.mac_address().0 }
+    fn device_name(&self) -> MyoString { MyoString::from(self.0.device_name()) }
+    fn firmware_version(&self, component: VersionComponent) -> u32
+    {
+        match self.0
+        {
+            MyoEvent::Paired(_, ref f) | MyoEvent::Connected(_, ref f) => match component
+            {
+                VersionComponent::Major => f.major,
+                VersionComponent::Minor => f.minor,
+                VersionComponent::Patch => f.patch,
+                VersionComponent::HardwareRevision => f.hardware_revision as u32
+            },
+            _ => 0
+        }
+    }
+    fn arm(&self) -> Arm { if let MyoEvent::ArmSynced(_, ref s) = self.0 { s.arm } else { Arm::Unknown } }
+    fn x_direction(&self) -> XDirection { if let MyoEvent::ArmSynced(_, ref s) = self.0 { s.x_direction } else { XDirection::Unknown } }
+    fn warmup_state(&self) -> WarmupState { if let MyoEvent::ArmSynced(_, ref s) = self.0 { s.warmup_state } else { WarmupState::Unknown } }
+    fn warmup_result(&self) -> WarmupResult { if let MyoEvent::WarmupCompleted(_, r) = self.0 { r } else { WarmupResult::Unknown } }
+    fn rotation_on_arm(&self) -> f32 { if let MyoEvent::ArmSynced(_, ref s) = self.0 { s.rotation_on_arm } else { 0.0 } }
+    fn orientation(&self, index: OrientationIndex) -> f32
+    {
+        match self.0
+        {
+            MyoEvent::Orientation(_, ref d) => match index
+            {
+                OrientationIndex::X => d.orientation.x, OrientationIndex::Y => d.orientation.y,
+                OrientationIndex::Z => d.orientation.z, OrientationIndex::W => d.orientation.w
+            },
+            _ => 0.0
+        }
+    }
+    fn accelerometer(&self, index: u32) -> f32
+    {
+        if let MyoEvent::Orientation(_, ref d) = self.0 { <[f32; 3]>::from(d.accelerometer).get(index as usize).cloned().unwrap_or(0.0) } else { 0.0 }
+    }
+    fn gyroscope(&self, index: u32) -> f32
+    {
+        if let MyoEvent::Orientation(_, ref d) = self.0 { <[f32; 3]>::from(d.gyroscope).get(index as usize).cloned().unwrap_or(0.0) } else { 0.0 }
+    }
+    fn pose(&self) -> Pose { if let MyoEvent::Pose(_, p) = self.0 { p } else { Pose::unknown } }
+    fn rssi(&self) -> i8 { if let MyoEvent::RSSI(_, r) = self.0 { r } else { 0 } }
+    fn battery_level(&self) -> u8 { if let MyoEvent::BatteryLevel(_, b) = self.0 { b } else { 0 } }
+    fn emg(&self, sensor: u32) -> i8 { if let MyoEvent::EMG(_, ref e) = self.0 { e.get(sensor as usize).cloned().unwrap_or(0) } else { 0 } }
+}
@@ -0,0 +1,51 @@
+//! Per-Armband State of Derived-Value Listeners
+
+use {HandlerResult, MACAddress, IntoHandlerResult};
+
+type OutputHandler<'h, O> = dyn FnMut(MACAddress, &O) -> HandlerResult + 'h;
+
+/// State of every armband keyed by MAC address, and the closures notified of the values derived from it.
+/// Listeners like `ForearmTracker` keep their state here and expose its handlers through their own builder.
+pub(crate) struct PerDevice<'h, S, O>
+{
+    states: Vec<(MACAddress, S)>,
+    handlers: Vec<Box<OutputHandler<'h, O>>>
+}
+impl<'h, S, O> PerDevice<'h, S, O>
+{
+    pub fn new() -> Self { PerDevice { states: Vec::new(), handlers: Vec::new() } }
+    /// Call `handler` with each output of `notify`
+    pub fn on_output<F, R>(&mut self, mut handler: F) where F: FnMut(MACAddress, &O) -> R + 'h, R: IntoHandlerResult
+    {
+        self.handlers.push(Box::new(move |m, o| handler(m, o).into_handler_result()));
+    }
+
+    /// State of an armband
+    pub fn get(&self, mac_address: MACAddress) -> Option<&S>
+    {
+        self.states.iter().find(|s| s.0 == mac_address).map(|s| &s.1)
+    }
+    /// Mutable state of an armband
+    pub fn get_mut(&mut self, mac_address: MACAddress) -> Option<&mut S>
+    {
+        self.states.iter_mut().find(|s| s.0 == mac_address).map(|s| &mut s.1)
+    }
+    /// State of an armband, created by `init` the first time
+    pub fn entry<F: FnOnce() -> S>(&mut self, mac_address: MACAddress, init: F) -> &mut S
+    {
+        let index = match self.states.iter().position(|s| s.0 == mac_address)
+        {
+            Some(n) => n,
+            None => { self.states.push((mac_address, init())); self.states.len() - 1 }
+        };
+        &mut self.states[index].1
+    }
+    /// Drop the state of an armband
+    pub fn remove(&mut self, mac_address: MACAddress) { self.states.retain(|s| s.0 != mac_address); }
+
+    /// Call the handlers in order of registration; `Stop` if any of them returns it
+    pub fn notify(&mut self, mac_address: MACAddress, output: &O) -> HandlerResult
+    {
+        self.handlers.iter_mut().fold(HandlerResult::Continue, |r, h| r.combine(h(mac_address, output)))
+    }
+}
@@ -0,0 +1,142 @@
+//! Polling, Iterator and Channel based Event API
+
+use {Hub, Backend, Result, EventListener, Event, HandlerResult, MyoEvent};
+use {PairedEvent, UnpairedEvent, ConnectedEvent, DisconnectedEvent, ArmSyncedEvent, ArmUnsyncedEvent, OrientationEvent,
+    PoseEvent, RSSIEvent, UnlockedEvent, LockedEvent, EMGEvent, BatteryLevelEvent, WarmupCompletedEvent};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread::JoinHandle;
+
+/// Event Listener passing every event to a closure as an owned value
+struct OwnedEventListener<F: FnMut(MyoEvent) -> HandlerResult>(F);
+macro_rules! DefOwnedEventListener
+{
+    ($($handler: ident($ev: ident) = $_t: ident),*) =>
+    {
+        impl<F: FnMut(MyoEvent) -> HandlerResult> EventListener for OwnedEventListener<F>
+        {
+            $(fn $handler(&mut self, event: $ev) -> HandlerResult { (self.0)(event.to_owned_event()) })*
+        }
+    }
+}
+ForEachEventHandler!(DefOwnedEventListener);
+
+impl<B: Backend> Hub<B>
+{
+    /// Process Events for up to approximately `duration_ms` milliseconds and return them in order
+    pub fn poll(&self, duration_ms: u32) -> Result<Vec<MyoEvent>>
+    {
+        let mut events = Vec::new();
+        self.run(duration_ms, &mut OwnedEventListener(|e| { events.push(e); HandlerResult::Continue }))?;
+        Ok(events)
+    }
+    /// Endless iterator of events, polling the hub every `slice_ms` milliseconds.
+    /// Blocks while no events arrive, and ends after yielding an error.
+    pub fn events(&self, slice_ms: u32) -> Events<'_, B>
+    {
+        Events { hub: self, slice_ms, buffer: VecDeque::new(), failed: false }
+    }
+}
+impl<B: Backend + Send + 'static> Hub<B>
+{
+    /// Move the hub to a background thread that runs it in slices of `slice_ms` milliseconds,
+    /// delivering events through a channel.
+    pub fn spawn(self, slice_ms: u32) -> HubThread<B>
+    {
+        let (sender, receiver) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        let thread = std::thread::spawn(move ||
+        {
+            let mut disconnected = false;
+            while !stop_flag.load(Ordering::Acquire) && !disconnected
+            {
+                self.run(slice_ms, &mut OwnedEventListener(|e|
+                {
+                    disconnected = sender.send(e).is_err();
+                    if disconnected { HandlerResult::Stop } else { HandlerResult::Continue }
+                }))?;
+            }
+            Ok(self)
+        });
+        HubThread { receiver, stop, thread: Some(thread) }
+    }
+}
+
+/// Iterator of events(`Hub::events`)
+pub struct Events<'h, B: Backend + 'h>
+{
+    hub: &'h Hub<B>, slice_ms: u32, buffer: VecDeque<MyoEvent>, failed: bool
+}
+impl<'h, B: Backend + 'h> Iterator for Events<'h, B>
+{
+    type Item = Result<MyoEvent>;
+    fn next(&mut self) -> Option<Result<MyoEvent>>
+    {
+        loop
+        {
+            if let Some(e) = self.buffer.pop_front() { return Some(Ok(e)); }
+            if self.failed { return None; }
+            match self.hub.poll(self.slice_ms)
+            {
+                Ok(events) => self.buffer.extend(events),
+                Err(e) => { self.failed = true; return Some(Err(e)); }
+            }
+        }
+    }
+}
+
+/// Hub running on a background thread(`Hub::spawn`).
+/// The thread stops on an error, or when the `HubThread` is stopped or dropped.
+/// Dropping it shuts the hub down(`Hub::shutdown`) and only logs a failure or a panic of the thread.
+pub struct HubThread<B: Backend>
+{
+    receiver: Receiver<MyoEvent>, stop: Arc<AtomicBool>, thread: Option<JoinHandle<Result<Hub<B>>>>
+}
+impl<B: Backend> HubThread<B>
+{
+    /// Receiver of events
+    pub fn receiver(&self) -> &Receiver<MyoEvent> { &self.receiver }
+    /// Stop the thread after the current slice and take back the hub.
+    /// Returns the error that stopped the thread early, if any; a panic of the thread is resumed.
+    pub fn stop(mut self) -> Result<Hub<B>>
+    {
+        match self.join().expect("the hub thread has been joined already")
+        {
+            Ok(r) => r,
+            Err(p) => std::panic::resume_unwind(p)
+        }
+    }
+    /// Stop the thread after the current slice and shut the hub down(`Hub::shutdown`)
+    pub fn shutdown(self) -> Result<()> { self.stop()?.shutdown() }
+
+    fn join(&mut self) -> Option<std::thread::Result<Result<Hub<B>>>>
+    {
+        self.stop.store(true, Ordering::Release);
+        self.thread.take().map(JoinHandle::join)
+    }
+}
+impl<B: Backend> Drop for HubThread<B>
+{
+    fn drop(&mut self)
+    {
+        // do not wait for the thread while unwinding
+        if std::thread::panicking() { self.stop.store(true, Ordering::Release); return; }
+        match self.join()
+        {
+            Some(Ok(Ok(hub))) => if let Err(e) = hub.shutdown() { eprintln!("myo: failed to shut down the hub: {}", e); },
+            Some(Ok(Err(e))) => eprintln!("myo: the hub thread stopped with an error: {}", e),
+            // unlike `stop`, dropping must not panic
+            Some(Err(p)) => eprintln!("myo: the hub thread panicked: {}", panic_message(&*p)),
+            None => ()
+        }
+    }
+}
+
+/// Message of a panic payload
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str
+{
+    payload.downcast_ref::<&str>().cloned().or_else(|| payload.downcast_ref::<String>().map(String::as_str)).unwrap_or("unknown panic payload")
+}
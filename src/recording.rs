@@ -0,0 +1,434 @@
+//! Binary Session Recording
+//!
+//! A recording starts with the magic `MYOR`, the format version(u16) and a header frame,
+//! followed by one frame per record. Every frame is `tag(u8) length(u16) payload crc32(u32)`
+//! with all integers in little endian, so that a frame torn by a crash is detected and dropped by the reader.
+//! Devices are announced by device frames before their first event, and events refer to them by index.
+
+use {EventListener, EventSink, Quaternion, Vec3, Event, HandlerResult, MyoEvent, EventInfo, EventType, MACAddress, FirmwareVersion, ArmSync, OrientationData,
+    HardwareRevision, Arm, XDirection, WarmupState, WarmupResult, Pose, DeviceState};
+use {PairedEvent, UnpairedEvent, ConnectedEvent, DisconnectedEvent, ArmSyncedEvent, ArmUnsyncedEvent, OrientationEvent,
+    PoseEvent, RSSIEvent, UnlockedEvent, LockedEvent, EMGEvent, BatteryLevelEvent, WarmupCompletedEvent};
+use std::io::{self, Read, Write, Seek, SeekFrom, BufReader, BufWriter};
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"MYOR";
+/// Version of the recording format written by this crate
+pub const FORMAT_VERSION: u16 = 1;
+const TAG_HEADER: u8 = 0x80;
+const TAG_DEVICE: u8 = 0x81;
+/// Event timestamps(in microseconds) between automatic flushes of a `Recorder`
+const FLUSH_INTERVAL: u64 = 1_000_000;
+
+/// Armband described in a recording
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedDevice
+{
+    /// MAC Address
+    pub mac_address: MACAddress,
+    /// Myo Name
+    pub name: String,
+    /// Firmware Version, if known
+    pub firmware: Option<FirmwareVersion>
+}
+impl<'a> From<&'a DeviceState> for RecordedDevice
+{
+    fn from(s: &'a DeviceState) -> Self
+    {
+        RecordedDevice { mac_address: s.mac_address, name: s.name.clone(), firmware: s.firmware }
+    }
+}
+
+/// Header of a recording
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingHeader
+{
+    /// Format Version
+    pub format_version: u16,
+    /// Application Identifier of the recording hub
+    pub application_identifier: String,
+    /// Version of the crate that wrote the recording
+    pub recorder_version: String,
+    /// Armbands known when the recording started
+    pub devices: Vec<RecordedDevice>
+}
+
+fn invalid<T>(message: &str) -> io::Result<T> { Err(io::Error::new(io::ErrorKind::InvalidData, message)) }
+/// Value of a known enum integer
+fn known<T>(value: Option<T>, message: &str) -> io::Result<T> { value.map_or_else(|| invalid(message), Ok) }
+
+fn crc32(data: &[u8]) -> u32
+{
+    let mut c = !0u32;
+    for &b in data
+    {
+        c ^= b as u32;
+        for _ in 0 .. 8 { c = if c & 1 != 0 { (c >> 1) ^ 0xedb8_8320 } else { c >> 1 }; }
+    }
+    !c
+}
+
+/// A length or count as u16, failing with `InvalidInput` beyond `u16::MAX`
+fn length(n: usize, message: &str) -> io::Result<u16>
+{
+    if n > u16::MAX as usize { Err(io::Error::new(io::ErrorKind::InvalidInput, message.to_owned())) } else { Ok(n as u16) }
+}
+
+/// Payload Builder
+struct Encoder(Vec<u8>);
+impl Encoder
+{
+    fn u8(&mut self, v: u8) -> &mut Self { self.0.push(v); self }
+    fn u16(&mut self, v: u16) -> &mut Self { self.0.extend_from_slice(&v.to_le_bytes()); self }
+    fn u32(&mut self, v: u32) -> &mut Self { self.0.extend_from_slice(&v.to_le_bytes()); self }
+    fn u64(&mut self, v: u64) -> &mut Self { self.0.extend_from_slice(&v.to_le_bytes()); self }
+    fn f32(&mut self, v: f32) -> &mut Self { self.0.extend_from_slice(&v.to_le_bytes()); self }
+    fn str(&mut self, v: &str) -> io::Result<&mut Self>
+    {
+        self.u16(length(v.len(), "string is too long")?); self.0.extend_from_slice(v.as_bytes()); Ok(self)
+    }
+    fn firmware(&mut self, f: &FirmwareVersion) -> &mut Self
+    {
+        self.u32(f.major).u32(f.minor).u32(f.patch).u32(f.hardware_revision as u32)
+    }
+    fn device(&mut self, d: &RecordedDevice) -> io::Result<&mut Self>
+    {
+        self.u64(d.mac_address.0).str(&d.name)?;
+        Ok(match d.firmware { Some(ref f) => self.u8(1).firmware(f), None => self.u8(0) })
+    }
+}
+
+/// Payload Reader
+struct Decoder<'a>(&'a [u8]);
+impl<'a> Decoder<'a>
+{
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]>
+    {
+        if self.0.len() < n { return invalid("record payload is too short"); }
+        let (h, t) = self.0.split_at(n); self.0 = t; Ok(h)
+    }
+    fn u8(&mut self) -> io::Result<u8> { self.take(1).map(|b| b[0]) }
+    fn u16(&mut self) -> io::Result<u16> { self.take(2).map(|b| u16::from_le_bytes([b[0], b[1]])) }
+    fn u32(&mut self) -> io::Result<u32> { self.take(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])) }
+    fn u64(&mut self) -> io::Result<u64> { Ok(self.u32()? as u64 | (self.u32()? as u64) << 32) }
+    fn f32(&mut self) -> io::Result<f32> { self.u32().map(f32::from_bits) }
+    fn str(&mut self) -> io::Result<String>
+    {
+        let n = self.u16()? as usize;
+        String::from_utf8(self.take(n)?.to_vec()).or_else(|_| invalid("string is not valid UTF-8"))
+    }
+    fn firmware(&mut self) -> io::Result<FirmwareVersion>
+    {
+        let (major, minor, patch) = (self.u32()?, self.u32()?, self.u32()?);
+        let hardware_revision = known(HardwareRevision::from_raw(self.u32()?), "unknown hardware revision")?;
+        Ok(FirmwareVersion { major, minor, patch, hardware_revision })
+    }
+    fn device(&mut self) -> io::Result<RecordedDevice>
+    {
+        let (mac_address, name) = (MACAddress(self.u64()?), self.str()?);
+        let firmware = if self.u8()? != 0 { Some(self.firmware()?) } else { None };
+        Ok(RecordedDevice { mac_address, name, firmware })
+    }
+}
+
+fn write_frame<W: Write>(w: &mut W, tag: u8, payload: &[u8]) -> io::Result<()>
+{
+    let length = length(payload.len(), "record is too large")?;
+    let mut frame = Vec::with_capacity(payload.len() + 7);
+    frame.push(tag);
+    frame.extend_from_slice(&length.to_le_bytes());
+    frame.extend_from_slice(payload);
+    let crc = crc32(&frame);
+    frame.extend_from_slice(&crc.to_le_bytes());
+    w.write_all(&frame)
+}
+/// Read a frame, `None` at the end of the data or at a torn frame
+fn read_frame<R: Read>(r: &mut R) -> io::Result<Option<(u8, Vec<u8>)>>
+{
+    let mut head = [0u8; 3];
+    if !read_full(r, &mut head)? { return Ok(None); }
+    let mut rest = vec![0u8; u16::from_le_bytes([head[1], head[2]]) as usize + 4];
+    if !read_full(r, &mut rest)? { return Ok(None); }
+    let n = rest.len() - 4;
+    let crc = u32::from_le_bytes([rest[n], rest[n + 1], rest[n + 2], rest[n + 3]]);
+    let mut frame = head.to_vec(); frame.extend_from_slice(&rest[.. n]);
+    if crc32(&frame) != crc { return Ok(None); }
+    rest.truncate(n);
+    Ok(Some((head[0], rest)))
+}
+/// `read_exact` that reports a short read as `false`
+fn read_full<R: Read>(r: &mut R, buf: &mut [u8]) -> io::Result<bool>
+{
+    let mut filled = 0;
+    while filled < buf.len()
+    {
+        match r.read(&mut buf[filled ..])
+        {
+            Ok(0) => return Ok(false),
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e)
+        }
+    }
+    Ok(true)
+}
+
+fn encode_event(e: &mut Encoder, event: &MyoEvent)
+{
+    match *event
+    {
+        MyoEvent::Paired(_, ref f) | MyoEvent::Connected(_, ref f) => { e.firmware(f); },
+        MyoEvent::ArmSynced(_, ref s) =>
+        {
+            e.u8(s.arm as u8).u8(s.x_direction as u8).u8(s.warmup_state as u8).f32(s.rotation_on_arm);
+        },
+        MyoEvent::Orientation(_, ref d) =>
+        {
+            let (q, a, g) = (d.orientation, d.accelerometer, d.gyroscope);
+            e.f32(q.x).f32(q.y).f32(q.z).f32(q.w).f32(a.x).f32(a.y).f32(a.z).f32(g.x).f32(g.y).f32(g.z);
+        },
+        MyoEvent::Pose(_, p) => { e.u16(p as u16); },
+        MyoEvent::RSSI(_, r) => { e.u8(r as u8); },
+        MyoEvent::EMG(_, ref v) => for &x in v { e.u8(x as u8); },
+        MyoEvent::BatteryLevel(_, b) => { e.u8(b); },
+        MyoEvent::WarmupCompleted(_, r) => { e.u8(r as u8); },
+        MyoEvent::Unpaired(_) | MyoEvent::Disconnected(_) | MyoEvent::ArmUnsynced(_) | MyoEvent::Unlocked(_) | MyoEvent::Locked(_) => ()
+    }
+}
+fn decode_event(d: &mut Decoder, t: EventType, info: EventInfo) -> io::Result<MyoEvent>
+{
+    Ok(match t
+    {
+        EventType::Paired => MyoEvent::Paired(info, d.firmware()?),
+        EventType::Unpaired => MyoEvent::Unpaired(info),
+        EventType::Connected => MyoEvent::Connected(info, d.firmware()?),
+        EventType::Disconnected => MyoEvent::Disconnected(info),
+        EventType::ArmSynced =>
+        {
+            let arm = known(Arm::from_raw(d.u8()? as _), "unknown arm")?;
+            let x_direction = known(XDirection::from_raw(d.u8()? as _), "unknown x direction")?;
+            let warmup_state = known(WarmupState::from_raw(d.u8()? as _), "unknown warmup state")?;
+            MyoEvent::ArmSynced(info, ArmSync { arm, x_direction, warmup_state, rotation_on_arm: d.f32()? })
+        },
+        EventType::ArmUnsynced => MyoEvent::ArmUnsynced(info),
+        EventType::Orientation =>
+        {
+            let mut v = [0.0; 10];
+            for x in v.iter_mut() { *x = d.f32()?; }
+            MyoEvent::Orientation(info, OrientationData
+            {
+                orientation: Quaternion::new(v[0], v[1], v[2], v[3]), accelerometer: Vec3::new(v[4], v[5], v[6]), gyroscope: Vec3::new(v[7], v[8], v[9])
+            })
+        },
+        EventType::Pose => MyoEvent::Pose(info, known(Pose::from_raw(d.u16()? as _), "unknown pose")?),
+        EventType::RSSI => MyoEvent::RSSI(info, d.u8()? as i8),
+        EventType::Unlocked => MyoEvent::Unlocked(info),
+        EventType::Locked => MyoEvent::Locked(info),
+        EventType::EMG =>
+        {
+            let mut v = [0; 8];
+            for x in v.iter_mut() { *x = d.u8()? as i8; }
+            MyoEvent::EMG(info, v)
+        },
+        EventType::BatteryLevel => MyoEvent::BatteryLevel(info, d.u8()?),
+        EventType::WarmupCompleted => MyoEvent::WarmupCompleted(info, known(WarmupResult::from_raw(d.u8()? as _), "unknown warmup result")?)
+    })
+}
+
+/// Event Listener writing every event to a recording.
+///
+/// Data is flushed once per second of event time, by `flush`/`finish`, and when a file recorder is dropped;
+/// a crash loses at most the unflushed records. Write errors stop the run and are kept in `error`.
+pub struct Recorder<W: Write = BufWriter<File>>
+{
+    writer: W, devices: Vec<RecordedDevice>, last_flush: Option<u64>, error: Option<io::Error>
+}
+impl Recorder
+{
+    /// Create a recording file, replacing an existing one
+    pub fn create<P: AsRef<Path>>(path: P, application_identifier: &str, devices: &[RecordedDevice]) -> io::Result<Self>
+    {
+        Recorder::new(BufWriter::new(File::create(path)?), application_identifier, devices)
+    }
+    /// Continue an existing recording file, dropping a torn record at its end
+    pub fn append<P: AsRef<Path>>(path: P) -> io::Result<Self>
+    {
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let (devices, length) =
+        {
+            let mut reader = RecordingReader::new(BufReader::new(&mut file))?;
+            while reader.next_event()?.is_some() {}
+            (reader.devices, reader.position)
+        };
+        file.set_len(length)?;
+        file.seek(SeekFrom::End(0))?;
+        Ok(Recorder { writer: BufWriter::new(file), devices, last_flush: None, error: None })
+    }
+}
+impl<W: Write> Recorder<W>
+{
+    /// Start a recording on `writer` with the armbands known so far(e.g. from `DeviceRegistry::devices`)
+    pub fn new(mut writer: W, application_identifier: &str, devices: &[RecordedDevice]) -> io::Result<Self>
+    {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        let mut e = Encoder(Vec::new());
+        e.str(application_identifier)?.str(env!("CARGO_PKG_VERSION"))?.u16(length(devices.len(), "too many devices")?);
+        for d in devices { e.device(d)?; }
+        write_frame(&mut writer, TAG_HEADER, &e.0)?;
+        writer.flush()?;
+        Ok(Recorder { writer, devices: devices.to_vec(), last_flush: None, error: None })
+    }
+    /// Append an event, announcing its device first if needed
+    pub fn write_event(&mut self, event: &MyoEvent) -> io::Result<()>
+    {
+        let info = event.info();
+        length(info.device_name.len(), "device name is too long")?;
+        let firmware = match *event { MyoEvent::Paired(_, f) | MyoEvent::Connected(_, f) => Some(f), _ => None };
+        let (index, new) = match self.devices.iter().position(|d| d.mac_address == info.mac_address)
+        {
+            Some(n) => (n, false),
+            None =>
+            {
+                length(self.devices.len(), "too many devices")?;
+                self.devices.push(RecordedDevice { mac_address: info.mac_address, name: String::new(), firmware: None });
+                (self.devices.len() - 1, true)
+            }
+        };
+        let changed =
+        {
+            let d = &mut self.devices[index];
+            let renamed = !info.device_name.is_empty() && d.name != info.device_name;
+            if renamed { d.name = info.device_name.clone(); }
+            let updated = firmware.is_some() && d.firmware != firmware;
+            if updated { d.firmware = firmware; }
+            new || renamed || updated
+        };
+        if changed
+        {
+            let mut e = Encoder(Vec::new());
+            e.u16(index as u16).device(&self.devices[index])?;
+            write_frame(&mut self.writer, TAG_DEVICE, &e.0)?;
+        }
+        let mut e = Encoder(Vec::new());
+        e.u64(info.timestamp).u16(index as u16);
+        encode_event(&mut e, event);
+        write_frame(&mut self.writer, event.event_type() as u8, &e.0)?;
+        match self.last_flush
+        {
+            Some(t) if info.timestamp < t + FLUSH_INTERVAL && info.timestamp >= t => Ok(()),
+            _ => { self.last_flush = Some(info.timestamp); self.writer.flush() }
+        }
+    }
+    /// Write buffered records
+    pub fn flush(&mut self) -> io::Result<()> { self.writer.flush() }
+    /// First write error that stopped the recording as a listener
+    pub fn error(&self) -> Option<&io::Error> { self.error.as_ref() }
+    /// Flush and take back the writer
+    pub fn finish(mut self) -> io::Result<W>
+    {
+        if let Some(e) = self.error.take() { return Err(e); }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn record<E: Event>(&mut self, event: &E) -> HandlerResult
+    {
+        if self.error.is_some() { return HandlerResult::Stop; }
+        match self.write_event(&event.to_owned_event())
+        {
+            Ok(()) => HandlerResult::Continue,
+            Err(e) => { self.error = Some(e); HandlerResult::Stop }
+        }
+    }
+}
+macro_rules! DefRecorderListener
+{
+    ($($handler: ident($ev: ident) = $_t: ident),*) =>
+    {
+        impl<W: Write> EventListener for Recorder<W>
+        {
+            $(fn $handler(&mut self, event: $ev) -> HandlerResult { self.record(&event) })*
+        }
+    }
+}
+ForEachEventHandler!(DefRecorderListener);
+
+/// Reader of a recording, iterating over its events.
+/// Iteration ends at the end of the data or at a torn record left by a crash.
+pub struct RecordingReader<R: Read = BufReader<File>>
+{
+    reader: R, header: RecordingHeader, devices: Vec<RecordedDevice>, position: u64
+}
+impl RecordingReader
+{
+    /// Open a recording file
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> { RecordingReader::new(BufReader::new(File::open(path)?)) }
+}
+impl<R: Read> RecordingReader<R>
+{
+    /// Read the header of a recording
+    pub fn new(mut reader: R) -> io::Result<Self>
+    {
+        let mut magic = [0u8; 6];
+        if !read_full(&mut reader, &mut magic)? || &magic[.. 4] != MAGIC { return invalid("not a myo recording"); }
+        let format_version = u16::from_le_bytes([magic[4], magic[5]]);
+        if format_version > FORMAT_VERSION { return invalid("unsupported recording format version"); }
+        let payload = match read_frame(&mut reader)?
+        {
+            Some((TAG_HEADER, p)) => p,
+            _ => return invalid("broken recording header")
+        };
+        let mut d = Decoder(&payload);
+        let (application_identifier, recorder_version) = (d.str()?, d.str()?);
+        let devices = (0 .. d.u16()?).map(|_| d.device()).collect::<io::Result<Vec<_>>>()?;
+        let position = 6 + payload.len() as u64 + 7;
+        Ok(RecordingReader
+        {
+            reader, header: RecordingHeader { format_version, application_identifier, recorder_version, devices: devices.clone() },
+            devices, position
+        })
+    }
+    /// Header
+    pub fn header(&self) -> &RecordingHeader { &self.header }
+    /// Armbands known so far: the header's and those announced by the records read
+    pub fn devices(&self) -> &[RecordedDevice] { &self.devices }
+    /// Read the next event, `None` at the end
+    pub fn next_event(&mut self) -> io::Result<Option<MyoEvent>>
+    {
+        loop
+        {
+            let (tag, payload) = match read_frame(&mut self.reader)? { Some(f) => f, None => return Ok(None) };
+            self.position += payload.len() as u64 + 7;
+            let mut d = Decoder(&payload);
+            if tag == TAG_DEVICE
+            {
+                let (index, device) = (d.u16()? as usize, d.device()?);
+                if index < self.devices.len() { self.devices[index] = device; }
+                else if index == self.devices.len() { self.devices.push(device); }
+                else { return invalid("device announced out of order"); }
+                continue;
+            }
+            // records of newer types are skipped
+            let t = match EventType::from_raw(tag as _) { Some(t) => t, None => continue };
+            let timestamp = d.u64()?;
+            let device = match self.devices.get(d.u16()? as usize) { Some(d) => d, None => return invalid("record of an unknown device") };
+            let info = EventInfo { timestamp, mac_address: device.mac_address, device_name: device.name.clone() };
+            return decode_event(&mut d, t, info).map(Some);
+        }
+    }
+}
+impl<R: Read> Iterator for RecordingReader<R>
+{
+    type Item = io::Result<MyoEvent>;
+    fn next(&mut self) -> Option<io::Result<MyoEvent>> { self.next_event().transpose() }
+}
+impl<W: Write> EventSink for Recorder<W>
+{
+    fn flush(&mut self) -> io::Result<()>
+    {
+        if let Some(ref e) = self.error { return Err(io::Error::new(e.kind(), e.to_string())); }
+        Recorder::flush(self)
+    }
+}
@@ -0,0 +1,195 @@
+//! Per-Armband State Tracking
+
+use {EventListener, Event, HandlerResult, MACAddress, MyoEvent, FirmwareVersion, ArmSync, IntoHandlerResult};
+use {PairedEvent, UnpairedEvent, ConnectedEvent, DisconnectedEvent, ArmSyncedEvent, ArmUnsyncedEvent,
+    RSSIEvent, UnlockedEvent, LockedEvent, BatteryLevelEvent, OrientationEvent, PoseEvent, EMGEvent, WarmupCompletedEvent};
+
+/// Last known state of an armband
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceState
+{
+    /// MAC Address
+    pub mac_address: MACAddress,
+    /// Myo Name
+    pub name: String,
+    /// Paired with the hub
+    pub paired: bool,
+    /// Connected to the hub
+    pub connected: bool,
+    /// Firmware Version reported by the last Paired/Connected event
+    pub firmware: Option<FirmwareVersion>,
+    /// Arm sync information, `None` while not synced
+    pub arm_sync: Option<ArmSync>,
+    /// Lock state, `None` until the first Locked/Unlocked event
+    pub locked: Option<bool>,
+    /// Last battery level
+    pub battery_level: Option<u8>,
+    /// Last RSSI value
+    pub rssi: Option<i8>,
+    /// Timestamp of the last event that updated the state
+    pub last_update: u64
+}
+impl DeviceState
+{
+    fn new(mac_address: MACAddress) -> Self
+    {
+        DeviceState
+        {
+            mac_address, name: String::new(), paired: false, connected: false, firmware: None, arm_sync: None,
+            locked: None, battery_level: None, rssi: None, last_update: 0
+        }
+    }
+}
+
+/// Kind of change notified by `DeviceRegistry`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceChange
+{
+    /// `paired` became true
+    Paired,
+    /// `paired` became false
+    Unpaired,
+    /// `connected` became true
+    Connected,
+    /// `connected` became false
+    Disconnected,
+    /// `name` changed
+    Renamed,
+    /// `firmware` changed
+    Firmware,
+    /// `arm_sync` became or changed to another `Some` value
+    ArmSynced,
+    /// `arm_sync` became `None`
+    ArmUnsynced,
+    /// `locked` became `Some(true)`
+    Locked,
+    /// `locked` became `Some(false)`
+    Unlocked,
+    /// `battery_level` changed
+    BatteryLevel,
+    /// `rssi` changed
+    RSSI
+}
+
+type ChangeHandler<'r> = dyn FnMut(&DeviceState, DeviceChange) -> HandlerResult + 'r;
+
+/// Event Listener that tracks the state of every armband seen by the hub, keyed by MAC address.
+///
+/// Closures registered with `on_change` are called once for each change of a state, in order of registration,
+/// after the state has been updated; the run stops when any of them returns `HandlerResult::Stop`.
+/// Events that repeat a known value(e.g. the same battery level) do not notify.
+/// Disconnecting or unpairing an armband also clears its arm sync and lock state.
+/// An armband first seen through an event other than Paired, Unpaired or Disconnected(e.g. one connected before the hub started)
+/// is taken to be paired and connected, as `ArmbandHandle` does.
+pub struct DeviceRegistry<'r>
+{
+    devices: Vec<DeviceState>,
+    handlers: Vec<Box<ChangeHandler<'r>>>
+}
+impl<'r> DeviceRegistry<'r>
+{
+    /// Registry without any armbands
+    pub fn new() -> Self { DeviceRegistry { devices: Vec::new(), handlers: Vec::new() } }
+    /// Call `handler` with the updated state on each change
+    pub fn on_change<F, R>(mut self, mut handler: F) -> Self where F: FnMut(&DeviceState, DeviceChange) -> R + 'r, R: IntoHandlerResult
+    {
+        self.handlers.push(Box::new(move |s, c| handler(s, c).into_handler_result()));
+        self
+    }
+
+    /// State of an armband
+    pub fn device(&self, mac_address: MACAddress) -> Option<&DeviceState>
+    {
+        self.devices.iter().find(|d| d.mac_address == mac_address)
+    }
+    /// States of all armbands in order of appearance
+    pub fn devices(&self) -> &[DeviceState] { &self.devices }
+    /// Currently connected armbands
+    pub fn connected(&self) -> impl Iterator<Item = &DeviceState> { self.devices.iter().filter(|d| d.connected) }
+    /// Copy of the states of all armbands, e.g. to send to another thread
+    pub fn snapshot(&self) -> Vec<DeviceState> { self.devices.clone() }
+
+    /// Apply an owned event, e.g. one received from `Hub::poll` or a stream.
+    /// High-rate events(Orientation, EMG) and Pose/WarmupCompleted events do not change the state of a known armband.
+    pub fn update(&mut self, event: &MyoEvent) -> HandlerResult
+    {
+        let info = event.info();
+        let state_only = matches!(*event, MyoEvent::Orientation(..) | MyoEvent::EMG(..) | MyoEvent::Pose(..) | MyoEvent::WarmupCompleted(..));
+        let (index, old) = match self.devices.iter().position(|d| d.mac_address == info.mac_address)
+        {
+            Some(_) if state_only => return HandlerResult::Continue,
+            Some(n) => (n, self.devices[n].clone()),
+            None =>
+            {
+                // events other than Paired only arrive from connected armbands
+                let old = DeviceState::new(info.mac_address);
+                let connected = !matches!(*event, MyoEvent::Paired(..) | MyoEvent::Unpaired(..) | MyoEvent::Disconnected(..));
+                self.devices.push(DeviceState { paired: true, connected, ..old.clone() });
+                (self.devices.len() - 1, old)
+            }
+        };
+        {
+            let d = &mut self.devices[index];
+            match *event
+            {
+                MyoEvent::Paired(_, f) => { d.paired = true; d.firmware = Some(f); },
+                MyoEvent::Unpaired(_) => { d.paired = false; d.connected = false; d.arm_sync = None; d.locked = None; },
+                MyoEvent::Connected(_, f) => { d.paired = true; d.connected = true; d.firmware = Some(f); },
+                MyoEvent::Disconnected(_) => { d.connected = false; d.arm_sync = None; d.locked = None; },
+                MyoEvent::ArmSynced(_, s) => d.arm_sync = Some(s),
+                MyoEvent::ArmUnsynced(_) => d.arm_sync = None,
+                MyoEvent::Locked(_) => d.locked = Some(true),
+                MyoEvent::Unlocked(_) => d.locked = Some(false),
+                MyoEvent::BatteryLevel(_, b) => d.battery_level = Some(b),
+                MyoEvent::RSSI(_, r) => d.rssi = Some(r),
+                MyoEvent::Orientation(..) | MyoEvent::EMG(..) | MyoEvent::Pose(..) | MyoEvent::WarmupCompleted(..) => ()
+            }
+            if !info.device_name.is_empty() && d.name != info.device_name { d.name = info.device_name.clone(); }
+            d.last_update = info.timestamp;
+        }
+        let new = &self.devices[index];
+        let changes = [
+            (!old.paired && new.paired, DeviceChange::Paired),
+            (old.paired && !new.paired, DeviceChange::Unpaired),
+            (!old.connected && new.connected, DeviceChange::Connected),
+            (old.connected && !new.connected, DeviceChange::Disconnected),
+            (old.name != new.name, DeviceChange::Renamed),
+            (old.firmware != new.firmware, DeviceChange::Firmware),
+            (new.arm_sync.is_some() && old.arm_sync != new.arm_sync, DeviceChange::ArmSynced),
+            (old.arm_sync.is_some() && new.arm_sync.is_none(), DeviceChange::ArmUnsynced),
+            (old.locked != Some(true) && new.locked == Some(true), DeviceChange::Locked),
+            (old.locked != Some(false) && new.locked == Some(false), DeviceChange::Unlocked),
+            (old.battery_level != new.battery_level, DeviceChange::BatteryLevel),
+            (old.rssi != new.rssi, DeviceChange::RSSI)
+        ];
+        let mut r = HandlerResult::Continue;
+        for &(_, c) in changes.iter().filter(|c| c.0)
+        {
+            for h in &mut self.handlers { r = r.combine(h(new, c)); }
+        }
+        r
+    }
+}
+impl<'r> Default for DeviceRegistry<'r>
+{
+    fn default() -> Self { Self::new() }
+}
+macro_rules! DefRegistryListener
+{
+    ($($handler: ident($ev: ident)),*; $($data_handler: ident($dev: ident)),*) =>
+    {
+        impl<'r> EventListener for DeviceRegistry<'r>
+        {
+            $(fn $handler(&mut self, event: $ev) -> HandlerResult { self.update(&event.to_owned_event()) })*
+            // only the first data event of an unknown armband changes the state; skip copying the rest
+            $(fn $data_handler(&mut self, event: $dev) -> HandlerResult
+            {
+                if self.device(event.mac_address()).is_some() { HandlerResult::Continue } else { self.update(&event.to_owned_event()) }
+            })*
+        }
+    }
+}
+DefRegistryListener!(on_paired(PairedEvent), on_unpaired(UnpairedEvent), on_connected(ConnectedEvent), on_disconnected(DisconnectedEvent),
+    on_arm_synced(ArmSyncedEvent), on_arm_unsynced(ArmUnsyncedEvent), on_rssi_value(RSSIEvent), on_unlocked(UnlockedEvent),
+    on_locked(LockedEvent), on_battery_level(BatteryLevelEvent);
+    on_orientation_data(OrientationEvent), on_pose(PoseEvent), on_emg_data(EMGEvent), on_warmup_completed(WarmupCompletedEvent));
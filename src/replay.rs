@@ -0,0 +1,152 @@
+//! Replay Backend for Recorded Sessions
+
+use {Result, LockingPolicy, HandlerResult, MACAddress, EventType, MyoEvent, RecordingReader};
+use backend::{Backend, BackendHandler, Device, Command};
+use owned::OwnedRawEvent;
+use std::cell::Cell;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Playback speed of a `ReplayBackend`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed
+{
+    /// Original timing of the events
+    Original,
+    /// Original timing accelerated by a factor(2.0 plays twice as fast)
+    Scaled(f64),
+    /// No delays between events
+    AsFastAsPossible
+}
+
+/// Commands executed on replayed armbands, shared with their retained devices
+type CommandLog = Arc<Mutex<Vec<(MACAddress, Command)>>>;
+
+/// Armband of a replayed event: commands succeed and are logged
+struct ReplayDevice { mac_address: MACAddress, log: CommandLog }
+impl Device for ReplayDevice
+{
+    fn raw_id(&self) -> usize { self.mac_address.0 as usize }
+    fn execute(&self, command: Command) -> Result<()>
+    {
+        self.log.lock().unwrap_or_else(|e| e.into_inner()).push((self.mac_address, command));
+        Ok(())
+    }
+    fn retain(&self) -> Box<dyn Device + Send> { Box::new(ReplayDevice { mac_address: self.mac_address, log: self.log.clone() }) }
+}
+
+/// Backend replaying a sequence of events, e.g. a recording, to a `Hub`:
+/// `Hub::with_backend(ReplayBackend::open("session.myor")?.speed(ReplaySpeed::Scaled(4.0)))`.
+///
+/// Like libmyo, `Hub::run` dispatches the events due within its duration and then waits for the rest of it.
+/// At the end of the events(without looping) a run only waits, or returns immediately at `AsFastAsPossible` speed.
+pub struct ReplayBackend
+{
+    events: Vec<MyoEvent>, speed: ReplaySpeed, looping: bool,
+    devices: Option<Vec<MACAddress>>, event_types: Option<Vec<EventType>>,
+    cursor: Cell<usize>, anchor: Cell<Option<(Instant, u64)>>, log: CommandLog
+}
+impl ReplayBackend
+{
+    /// Replay `events` in timestamp order at original speed.
+    /// Events are sorted by timestamp first(keeping the order of equal ones), since recordings of several armbands
+    /// or appended after a crash are not necessarily in order.
+    pub fn new(mut events: Vec<MyoEvent>) -> Self
+    {
+        events.sort_by_key(MyoEvent::timestamp);
+        ReplayBackend
+        {
+            events, speed: ReplaySpeed::Original, looping: false, devices: None, event_types: None,
+            cursor: Cell::new(0), anchor: Cell::new(None), log: Arc::new(Mutex::new(Vec::new()))
+        }
+    }
+    /// Replay a recording file
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self>
+    {
+        RecordingReader::open(path)?.collect::<io::Result<Vec<_>>>().map(ReplayBackend::new)
+    }
+    /// Set the playback speed, panics on a factor that is not positive
+    pub fn speed(mut self, speed: ReplaySpeed) -> Self
+    {
+        if let ReplaySpeed::Scaled(f) = speed { assert!(f > 0.0, "replay speed factor must be positive"); }
+        self.speed = speed; self
+    }
+    /// Restart from the first event after the last one
+    pub fn looping(mut self, looping: bool) -> Self { self.looping = looping; self }
+    /// Replay only the events of the armbands of `mac_addresses`
+    pub fn devices(mut self, mac_addresses: &[MACAddress]) -> Self { self.devices = Some(mac_addresses.to_vec()); self }
+    /// Replay only the events of `types`
+    pub fn event_types(mut self, types: &[EventType]) -> Self { self.event_types = Some(types.to_vec()); self }
+
+    /// Continue from the first event at or after `timestamp`
+    pub fn seek(&self, timestamp: u64)
+    {
+        self.cursor.set(self.events.iter().position(|e| e.timestamp() >= timestamp).unwrap_or(self.events.len()));
+        self.anchor.set(None);
+    }
+    /// Timestamp of the next event to replay
+    pub fn position(&self) -> Option<u64> { self.events.get(self.cursor.get()).map(MyoEvent::timestamp) }
+    /// Whether all events have been replayed(never while looping)
+    pub fn is_finished(&self) -> bool { self.next_index().is_none() }
+    /// Commands executed on the replayed armbands so far, in order
+    pub fn executed_commands(&self) -> Vec<(MACAddress, Command)> { self.log.lock().unwrap_or_else(|e| e.into_inner()).clone() }
+
+    fn accepts(&self, e: &MyoEvent) -> bool
+    {
+        self.devices.as_ref().is_none_or(|d| d.contains(&e.mac_address())) &&
+        self.event_types.as_ref().is_none_or(|t| t.contains(&e.event_type()))
+    }
+    /// Index of the next accepted event, wrapping around while looping
+    fn next_index(&self) -> Option<usize>
+    {
+        let from = self.cursor.get();
+        if let Some(n) = self.events[from.min(self.events.len()) ..].iter().position(|e| self.accepts(e)) { return Some(from + n); }
+        if !self.looping { return None; }
+        self.events[.. from.min(self.events.len())].iter().position(|e| self.accepts(e))
+    }
+}
+impl Backend for ReplayBackend
+{
+    fn set_locking_policy(&self, _locking_policy: LockingPolicy) -> Result<()> { Ok(()) }
+    fn run(&self, duration_ms: u32, handler: &mut BackendHandler) -> Result<()>
+    {
+        let deadline = Instant::now() + Duration::from_millis(duration_ms as u64);
+        let factor = match self.speed
+        {
+            ReplaySpeed::Original => Some(1.0),
+            ReplaySpeed::Scaled(f) => Some(f),
+            ReplaySpeed::AsFastAsPossible => None
+        };
+        let wait_until = |t: Instant| { let now = Instant::now(); if t > now { std::thread::sleep(t - now); } };
+        let mut dispatched = false;
+        loop
+        {
+            let index = match self.next_index()
+            {
+                Some(n) => n,
+                None => { if factor.is_some() { wait_until(deadline); } return Ok(()); }
+            };
+            // wrapped around: restart the clock
+            if index < self.cursor.get() { self.anchor.set(None); }
+            let event = &self.events[index];
+            match factor
+            {
+                Some(f) =>
+                {
+                    let (t0, ts0) = self.anchor.get().unwrap_or((Instant::now(), event.timestamp()));
+                    self.anchor.set(Some((t0, ts0)));
+                    let due = t0 + Duration::from_secs_f64(event.timestamp().saturating_sub(ts0) as f64 / 1_000_000.0 / f);
+                    if due > deadline { wait_until(deadline); return Ok(()); }
+                    wait_until(due);
+                },
+                None => if dispatched && Instant::now() >= deadline { return Ok(()); }
+            }
+            self.cursor.set(index + 1);
+            dispatched = true;
+            let device = ReplayDevice { mac_address: event.mac_address(), log: self.log.clone() };
+            if handler(&OwnedRawEvent(event.clone(), &device)) == HandlerResult::Stop { return Ok(()); }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Serde Support(`serde` feature)
+
+use {MACAddress, EventType, Arm, XDirection, WarmupState, WarmupResult, Pose, HardwareRevision, LockingPolicy, VibrationType, UnlockType};
+use {MyoEvent, EventInfo, FirmwareVersion, ArmSync, OrientationData, Quaternion};
+use names::EnumName;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+macro_rules! DefNamedSerde
+{
+    ($($t: ident),*) =>
+    {
+        $(
+            impl Serialize for $t
+            {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> { serializer.serialize_str(self.name()) }
+            }
+            impl<'de> Deserialize<'de> for $t
+            {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+                {
+                    let name = Cow::<str>::deserialize(deserializer)?;
+                    $t::from_name(&name).ok_or_else(|| D::Error::unknown_variant(&name, $t::NAMES))
+                }
+            }
+        )*
+    }
+}
+DefNamedSerde!(EventType, Arm, XDirection, WarmupState, WarmupResult, Pose, HardwareRevision, LockingPolicy, VibrationType, UnlockType);
+
+impl Serialize for MACAddress
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> { serializer.collect_str(self) }
+}
+impl<'de> Deserialize<'de> for MACAddress
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    {
+        let s = Cow::<str>::deserialize(deserializer)?;
+        s.parse().map_err(|_| D::Error::custom(format!("invalid MAC address {:?}", s)))
+    }
+}
+
+/// Serialized form of `MyoEvent`: the common fields and the fields of its type in one object
+#[derive(Serialize, Deserialize)]
+pub(crate) struct FlatEvent
+{
+    #[serde(rename = "type")] event_type: EventType,
+    timestamp: u64, mac: MACAddress, name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")] firmware: Option<FirmwareVersion>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] arm: Option<Arm>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] x_direction: Option<XDirection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] warmup_state: Option<WarmupState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] rotation_on_arm: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] orientation: Option<Quaternion>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] accelerometer: Option<[f32; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] gyroscope: Option<[f32; 3]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] pose: Option<Pose>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] rssi: Option<i8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] emg: Option<[i8; 8]>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] battery_level: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")] warmup_result: Option<WarmupResult>
+}
+impl From<MyoEvent> for FlatEvent
+{
+    fn from(e: MyoEvent) -> Self
+    {
+        let event_type = e.event_type();
+        let info = e.info().clone();
+        let mut f = FlatEvent
+        {
+            event_type, timestamp: info.timestamp, mac: info.mac_address, name: info.device_name,
+            firmware: None, arm: None, x_direction: None, warmup_state: None, rotation_on_arm: None,
+            orientation: None, accelerometer: None, gyroscope: None,
+            pose: None, rssi: None, emg: None, battery_level: None, warmup_result: None
+        };
+        match e
+        {
+            MyoEvent::Paired(_, v) | MyoEvent::Connected(_, v) => f.firmware = Some(v),
+            MyoEvent::ArmSynced(_, s) =>
+            {
+                f.arm = Some(s.arm);
+                f.x_direction = Some(s.x_direction);
+                f.warmup_state = Some(s.warmup_state);
+                f.rotation_on_arm = Some(s.rotation_on_arm);
+            },
+            MyoEvent::Orientation(_, d) =>
+            {
+                f.orientation = Some(d.orientation);
+                f.accelerometer = Some(d.accelerometer.into());
+                f.gyroscope = Some(d.gyroscope.into());
+            },
+            MyoEvent::Pose(_, p) => f.pose = Some(p),
+            MyoEvent::RSSI(_, r) => f.rssi = Some(r),
+            MyoEvent::EMG(_, v) => f.emg = Some(v),
+            MyoEvent::BatteryLevel(_, b) => f.battery_level = Some(b),
+            MyoEvent::WarmupCompleted(_, r) => f.warmup_result = Some(r),
+            MyoEvent::Unpaired(_) | MyoEvent::Disconnected(_) | MyoEvent::ArmUnsynced(_) | MyoEvent::Unlocked(_) | MyoEvent::Locked(_) => ()
+        }
+        f
+    }
+}
+/// Field required by the type of the event
+fn required<T>(value: Option<T>, key: &str) -> Result<T, String> { value.ok_or_else(|| format!("missing field `{}`", key)) }
+impl TryFrom<FlatEvent> for MyoEvent
+{
+    type Error = String;
+    fn try_from(f: FlatEvent) -> Result<Self, String>
+    {
+        let info = EventInfo { timestamp: f.timestamp, mac_address: f.mac, device_name: f.name };
+        Ok(match f.event_type
+        {
+            EventType::Paired => MyoEvent::Paired(info, required(f.firmware, "firmware")?),
+            EventType::Unpaired => MyoEvent::Unpaired(info),
+            EventType::Connected => MyoEvent::Connected(info, required(f.firmware, "firmware")?),
+            EventType::Disconnected => MyoEvent::Disconnected(info),
+            EventType::ArmSynced => MyoEvent::ArmSynced(info, ArmSync
+            {
+                arm: required(f.arm, "arm")?, x_direction: required(f.x_direction, "x_direction")?,
+                warmup_state: required(f.warmup_state, "warmup_state")?, rotation_on_arm: required(f.rotation_on_arm, "rotation_on_arm")?
+            }),
+            EventType::ArmUnsynced => MyoEvent::ArmUnsynced(info),
+            EventType::Orientation =>
+            {
+                let (q, a, g) = (required(f.orientation, "orientation")?, required(f.accelerometer, "accelerometer")?, required(f.gyroscope, "gyroscope")?);
+                MyoEvent::Orientation(info, OrientationData
+                {
+                    orientation: q, accelerometer: a.into(), gyroscope: g.into()
+                })
+            },
+            EventType::Pose => MyoEvent::Pose(info, required(f.pose, "pose")?),
+            EventType::RSSI => MyoEvent::RSSI(info, required(f.rssi, "rssi")?),
+            EventType::Unlocked => MyoEvent::Unlocked(info),
+            EventType::Locked => MyoEvent::Locked(info),
+            EventType::EMG => MyoEvent::EMG(info, required(f.emg, "emg")?),
+            EventType::BatteryLevel => MyoEvent::BatteryLevel(info, required(f.battery_level, "battery_level")?),
+            EventType::WarmupCompleted => MyoEvent::WarmupCompleted(info, required(f.warmup_result, "warmup_result")?)
+        })
+    }
+}
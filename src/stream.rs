@@ -0,0 +1,234 @@
+//! Asynchronous Event Stream(`stream` feature)
+
+use {Hub, Backend, Result, Error, EventListener, Event, EventType, HandlerResult, MyoEvent, MACAddress,
+    Command, VibrationType, CommandQueue};
+use {PairedEvent, UnpairedEvent, ConnectedEvent, DisconnectedEvent, ArmSyncedEvent, ArmUnsyncedEvent, OrientationEvent,
+    PoseEvent, RSSIEvent, UnlockedEvent, LockedEvent, EMGEvent, BatteryLevelEvent, WarmupCompletedEvent};
+use futures_core::Stream;
+use futures_channel::oneshot;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, Condvar};
+use std::task::{Context, Poll, Waker};
+use std::future::Future;
+use std::pin::Pin;
+use std::thread::JoinHandle;
+
+/// What to do with a high-rate event when the stream buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropPolicy
+{
+    /// Discard the incoming event
+    DropNewest,
+    /// Discard the oldest buffered event of the same type(the incoming one if there is none)
+    DropOldest,
+    /// Wait until the consumer makes room, like the other events do
+    Block
+}
+
+/// Configuration of `Hub::stream`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamConfig
+{
+    /// Maximum number of buffered events
+    pub capacity: usize,
+    /// Policy for EMG events(200 Hz)
+    pub emg: DropPolicy,
+    /// Policy for Orientation events(50 Hz)
+    pub orientation: DropPolicy,
+    /// Duration of each `Hub::run` slice on the hub thread
+    pub slice_ms: u32
+}
+impl Default for StreamConfig
+{
+    fn default() -> Self
+    {
+        StreamConfig { capacity: 256, emg: DropPolicy::DropOldest, orientation: DropPolicy::DropOldest, slice_ms: 50 }
+    }
+}
+
+struct Buffer
+{
+    events: VecDeque<MyoEvent>, error: Option<Error>, closed: bool, waker: Option<Waker>, dropped: u64
+}
+struct Shared { buffer: Mutex<Buffer>, space: Condvar }
+impl Shared
+{
+    fn lock(&self) -> std::sync::MutexGuard<'_, Buffer> { self.buffer.lock().unwrap_or_else(|e| e.into_inner()) }
+    fn wake(b: &mut Buffer) { if let Some(w) = b.waker.take() { w.wake(); } }
+}
+
+/// Event Listener on the hub thread: buffers events
+struct StreamListener<'s> { shared: &'s Shared, config: StreamConfig }
+impl<'s> StreamListener<'s>
+{
+    fn push(&mut self, event: MyoEvent) -> HandlerResult
+    {
+        let policy = match event.event_type()
+        {
+            EventType::EMG => self.config.emg,
+            EventType::Orientation => self.config.orientation,
+            _ => DropPolicy::Block
+        };
+        let mut b = self.shared.lock();
+        while !b.closed && b.events.len() >= self.config.capacity
+        {
+            match policy
+            {
+                DropPolicy::DropNewest => { b.dropped += 1; return HandlerResult::Continue; },
+                DropPolicy::DropOldest =>
+                {
+                    b.dropped += 1;
+                    let t = event.event_type();
+                    match b.events.iter().position(|e| e.event_type() == t)
+                    {
+                        Some(n) => { b.events.remove(n); },
+                        None => return HandlerResult::Continue
+                    }
+                },
+                DropPolicy::Block => b = self.shared.space.wait(b).unwrap_or_else(|e| e.into_inner())
+            }
+        }
+        if b.closed { return HandlerResult::Stop; }
+        b.events.push_back(event);
+        Shared::wake(&mut b);
+        HandlerResult::Continue
+    }
+}
+macro_rules! DefStreamListener
+{
+    ($($handler: ident($ev: ident) = $_t: ident),*) =>
+    {
+        impl<'s> EventListener for StreamListener<'s>
+        {
+            $(fn $handler(&mut self, event: $ev) -> HandlerResult { self.push(event.to_owned_event()) })*
+        }
+    }
+}
+ForEachEventHandler!(DefStreamListener);
+
+impl<B: Backend + Send + 'static> Hub<B>
+{
+    /// Move the hub to a dedicated thread and receive its events as a `Stream`.
+    /// The stream ends after yielding an error of the hub.
+    pub fn stream(self, config: StreamConfig) -> HubStream
+    {
+        let shared = Arc::new(Shared
+        {
+            buffer: Mutex::new(Buffer { events: VecDeque::new(), error: None, closed: false, waker: None, dropped: 0 }),
+            space: Condvar::new()
+        });
+        let commands = self.command_queue();
+        let thread_shared = shared.clone();
+        let thread = std::thread::spawn(move ||
+        {
+            let mut listener = StreamListener { shared: &thread_shared, config };
+            let r = loop
+            {
+                if thread_shared.lock().closed { break Ok(()); }
+                if let Err(e) = self.run(config.slice_ms, &mut listener) { break Err(e); }
+            };
+            {
+                let mut b = thread_shared.lock();
+                b.error = r.err();
+                b.closed = true;
+                Shared::wake(&mut b);
+            }
+            self.shutdown()
+        });
+        HubStream { shared, commands, thread: Some(thread) }
+    }
+}
+
+/// Stream of events from a hub thread(`Hub::stream`). Dropping it stops the thread.
+pub struct HubStream
+{
+    shared: Arc<Shared>, commands: CommandQueue, thread: Option<JoinHandle<Result<()>>>
+}
+impl HubStream
+{
+    /// Command handle for the armbands connected to the hub thread
+    pub fn commander(&self) -> StreamCommander { StreamCommander(self.commands.clone()) }
+    /// Number of high-rate events dropped so far
+    pub fn dropped(&self) -> u64 { self.shared.lock().dropped }
+    /// Stop the hub thread and shut the hub down(`Hub::shutdown`).
+    /// Unlike dropping the stream, this reports an error of the shutdown.
+    pub fn shutdown(mut self) -> Result<()>
+    {
+        self.close();
+        match self.thread.take().map(JoinHandle::join)
+        {
+            None => Ok(()),
+            Some(Ok(r)) => r,
+            Some(Err(p)) => std::panic::resume_unwind(p)
+        }
+    }
+
+    fn close(&self)
+    {
+        self.shared.lock().closed = true;
+        self.shared.space.notify_all();
+    }
+}
+impl Stream for HubStream
+{
+    type Item = Result<MyoEvent>;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<MyoEvent>>>
+    {
+        let mut b = self.shared.lock();
+        if let Some(e) = b.events.pop_front()
+        {
+            self.shared.space.notify_all();
+            return Poll::Ready(Some(Ok(e)));
+        }
+        if let Some(e) = b.error.take() { return Poll::Ready(Some(Err(e))); }
+        if b.closed { return Poll::Ready(None); }
+        b.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+impl Drop for HubStream
+{
+    fn drop(&mut self)
+    {
+        self.close();
+        if let Some(Ok(Err(e))) = self.thread.take().map(JoinHandle::join) { eprintln!("myo: failed to shut down the hub: {}", e); }
+    }
+}
+
+/// Cloneable handle to send armband commands to a hub thread from any task.
+/// Commands are executed between the run slices of the hub thread(see `CommandQueue`).
+#[derive(Clone)]
+pub struct StreamCommander(CommandQueue);
+impl StreamCommander
+{
+    /// Execute `command` on the armband with `mac_address`
+    pub fn send(&self, mac_address: MACAddress, command: Command) -> CommandReply
+    {
+        let (reply, receiver) = oneshot::channel();
+        // a stopped hub thread drops the reply sender, which is reported by `CommandReply`
+        self.0.send_with(mac_address, command, Box::new(move |r| { let _ = reply.send(r); }));
+        CommandReply(receiver)
+    }
+    /// Vibrate
+    pub fn vibrate(&self, mac_address: MACAddress, vtype: VibrationType) -> CommandReply
+    {
+        self.send(mac_address, Command::Vibrate(vtype))
+    }
+    /// Stream EMG Data
+    pub fn set_stream_emg(&self, mac_address: MACAddress, stream_emg_data: bool) -> CommandReply
+    {
+        self.send(mac_address, Command::SetStreamEMG(stream_emg_data))
+    }
+}
+
+/// Completion of a command sent by `StreamCommander`
+pub struct CommandReply(oneshot::Receiver<Result<()>>);
+impl Future for CommandReply
+{
+    type Output = Result<()>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<()>>
+    {
+        Pin::new(&mut self.0).poll(cx).map(|r| r.unwrap_or_else(|_|
+            Err(Error::Generic("the hub thread stopped before executing the command".to_owned()))))
+    }
+}
@@ -0,0 +1,22 @@
+//! Hub initialization when libmyo cannot be found.
+//! libmyo is loaded once per process, so this runs in its own test binary.
+#![cfg(feature = "dynamic")]
+
+extern crate myo;
+
+use myo::{Hub, Error};
+
+#[test]
+fn init_reports_a_missing_library()
+{
+    std::env::set_var("MYO_LIBRARY", "/nonexistent/libmyo-missing");
+    let e = Hub::init("com.example.missing-library").err().expect("initialized without libmyo");
+    match e
+    {
+        Error::LibraryMissing(ref message) =>
+        {
+            assert!(message.starts_with("failed to load libmyo from"), "{}", message);
+        },
+        e => panic!("unexpected error: {:?}", e)
+    }
+}
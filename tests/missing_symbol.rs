@@ -0,0 +1,28 @@
+//! Hub initialization when the loaded library is not libmyo.
+//! libmyo is loaded once per process, so this runs in its own test binary.
+#![cfg(feature = "dynamic")]
+
+extern crate myo;
+
+use myo::{Hub, Error};
+
+/// A system library that exports none of the `libmyo_*` functions
+#[cfg(target_os = "linux")] const NOT_LIBMYO: &str = "libm.so.6";
+#[cfg(target_os = "macos")] const NOT_LIBMYO: &str = "/usr/lib/libSystem.B.dylib";
+#[cfg(windows)] const NOT_LIBMYO: &str = "kernel32.dll";
+
+#[test]
+fn init_reports_a_missing_symbol()
+{
+    std::env::set_var("MYO_LIBRARY", NOT_LIBMYO);
+    let e = Hub::init("com.example.missing-symbol").err().expect("initialized without libmyo symbols");
+    match e
+    {
+        Error::LibraryMissing(ref message) =>
+        {
+            assert!(message.starts_with("libmyo symbol `libmyo_"), "{}", message);
+            assert!(message.contains("is missing"), "{}", message);
+        },
+        e => panic!("unexpected error: {:?}", e)
+    }
+}